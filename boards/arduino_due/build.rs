@@ -1,33 +1,79 @@
-//! This build script copies the `memory.x` file from the crate root into
-//! a directory where the linker can always find it at build time.
-//! For many projects this is optional, as the linker always searches the
-//! project root directory -- wherever `Cargo.toml` is. However, if you
+//! This build script copies the `memory.x` file matching the selected chip
+//! feature into a directory where the linker can always find it at build
+//! time. For many projects this is optional, as the linker always searches
+//! the project root directory -- wherever `Cargo.toml` is. However, if you
 //! are using a workspace or have a more complicated build setup, this
 //! build script becomes required. Additionally, by requesting that
-//! Cargo re-run the build script whenever `memory.x` is changed,
-//! updating `memory.x` ensures a rebuild of the application with the
-//! new memory settings.
-
-/*
-#[cfg(any(feature = "atsam3a4c", feature = "atsam3x4c", feature = "atsam3x4e"))]
-const LINKER_SCRIPT: &str = "memory_sam3_4.x";
-#[cfg(any(feature = "atsam3a8c", feature = "atsam3x8c", feature = "atsam3x8e", feature = "atsam3x8h"))]
-*/
-const LINKER_SCRIPT: &str = "memory_sam3_8.x";
+//! Cargo re-run the build script whenever the linker script is changed,
+//! updating it ensures a rebuild of the application with the new memory
+//! settings.
+//!
+//! The whole step is gated behind the `memory-x` feature (same pattern as
+//! `embassy-stm32`), so a workspace consumer that supplies its own
+//! `memory.x` can disable this crate's copy and pick theirs up instead.
+//!
+//! Independently, enabling the `defmt-rtt` feature also links in
+//! `defmt.x`, the linker script `defmt`'s global logger relies on to lay
+//! out its log-format section table (the same `-Tlink.x -Tdefmt.x`
+//! combination the `embassy`/`rp-hal` example configs use).
 
 fn main() {
-    // Put the linker script somewhere the linker can find it
-    let src_dir = std::path::PathBuf::from(
-        std::env::var_os("CARGO_MANIFEST_DIR").expect("Failed to locate project root directory"),
-    );
-    let link_dir = std::path::PathBuf::from(
-        std::env::var_os("OUT_DIR").expect("Failed to locate project build directory"),
-    );
-    std::fs::copy(src_dir.join(LINKER_SCRIPT), link_dir.join("memory.x"))
-        .expect("Failed copying linker script from project root to build directory");
-    println!("cargo:rustc-link-search={}", link_dir.to_string_lossy());
+    if cfg!(feature = "memory-x") {
+        let linker_script = linker_script_name();
+
+        // Put the linker script somewhere the linker can find it
+        let src_dir = std::path::PathBuf::from(
+            std::env::var_os("CARGO_MANIFEST_DIR")
+                .expect("Failed to locate project root directory"),
+        );
+        let link_dir = std::path::PathBuf::from(
+            std::env::var_os("OUT_DIR").expect("Failed to locate project build directory"),
+        );
+        std::fs::copy(src_dir.join(linker_script), link_dir.join("memory.x"))
+            .expect("Failed copying linker script from project root to build directory");
+        println!("cargo:rustc-link-search={}", link_dir.to_string_lossy());
+
+        // Only re-run the build script when the linker script is changed,
+        // instead of when any part of the source code changes.
+        println!("cargo:rerun-if-changed={}", linker_script);
+    }
+
+    if cfg!(feature = "defmt-rtt") {
+        println!("cargo:rustc-link-arg=-Tdefmt.x");
+    }
+}
+
+/// Picks the linker script matching whichever single chip feature is
+/// active, panicking (the closest a build script can get to a
+/// `compile_error!`) if zero or more than one is.
+fn linker_script_name() -> &'static str {
+    let enabled: Vec<&str> = [
+        cfg!(feature = "sam3a4c").then_some("sam3a4c"),
+        cfg!(feature = "sam3x4c").then_some("sam3x4c"),
+        cfg!(feature = "sam3x4e").then_some("sam3x4e"),
+        cfg!(feature = "sam3a8c").then_some("sam3a8c"),
+        cfg!(feature = "sam3x8c").then_some("sam3x8c"),
+        cfg!(feature = "sam3x8e").then_some("sam3x8e"),
+        cfg!(feature = "sam3x8h").then_some("sam3x8h"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let chip = match enabled.as_slice() {
+        [chip] => *chip,
+        [] => panic!(
+            "No chip feature enabled; pick exactly one of sam3a4c/sam3a8c/sam3x4c/sam3x4e/sam3x8c/sam3x8e/sam3x8h"
+        ),
+        multiple => panic!(
+            "Multiple chip features enabled ({}); pick exactly one of sam3a4c/sam3a8c/sam3x4c/sam3x4e/sam3x8c/sam3x8e/sam3x8h",
+            multiple.join(", ")
+        ),
+    };
 
-    // Only re-run the build script when memory.x is changed,
-    // instead of when any part of the source code changes.
-    println!("{}", format!("cargo:rerun-if-changed={}", LINKER_SCRIPT));
+    match chip {
+        "sam3a4c" | "sam3x4c" | "sam3x4e" => "memory_sam3_4.x",
+        "sam3a8c" | "sam3x8c" | "sam3x8e" | "sam3x8h" => "memory_sam3_8.x",
+        _ => unreachable!(),
+    }
 }
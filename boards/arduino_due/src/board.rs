@@ -0,0 +1,137 @@
+//! A higher-level, Arduino-compatible view of the board, built on top of the
+//! raw [`crate::Pins`] produced by `define_pins!`.
+//!
+//! `Pins` only hands out typed-but-unconfigured GPIO pins; `Board::new` goes
+//! one step further and muxes the pins the Due's own silkscreen/variant.cpp
+//! dedicate to a fixed peripheral into that peripheral's function, wiring up
+//! `Serial0`..`Serial3` ready to use. TWI/SPI/ADC/DAC don't have HAL drivers
+//! in this crate yet (see the doc comments on the affected fields below), so
+//! `Wire`/`Wire1`/an SPI master/`analog_read`/`analog_write` aren't provided
+//! here -- only their pins, already muxed to the correct peripheral function
+//! where that's possible without such a driver. The `usb-device`-gated
+//! `usb_id`/`usb_vbof` fields are the same story: muxed pins standing in for
+//! a CDC-ACM `UsbBusAllocator` until `hal::uotghs::device` grows one.
+
+use crate::pac;
+use crate::Pins;
+use hal::comm::{Uart0, Usart0, Usart1};
+
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+use hal::comm::Usart3;
+
+use hal::gpio::{Pa17, Pa18, Pa25, Pa26, Pa27, Pa28, Pb12, Pb13, PfA};
+
+#[cfg(feature = "usb-device")]
+use hal::gpio::{Pb10, Pb11};
+
+use crate::hal;
+
+/// The Due's four hardware serial ports plus the TWI/SPI pins, muxed and
+/// ready to use, built from the raw [`Pins`] and the U(S)ART peripherals.
+pub struct Board {
+    /// `Serial0`, the UART peripheral on `d0_rx0`/`d1_tx0`.
+    pub serial0: Uart0,
+    /// `Serial1`, USART0 on `d18_tx1`/`d19_rx1`.
+    pub serial1: Usart0,
+    /// `Serial2`, USART1 on `d16_tx2`/`d17_rx2`.
+    pub serial2: Usart1,
+    /// `Serial3`, USART3 on `d14_tx3`/`d15_rx3`. Only available on
+    /// `sam3_e`/`sam3x8h`; the `sam3_c` targets don't have USART3 at all.
+    #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+    pub serial3: Usart3,
+
+    /// `Wire`'s SDA line (TWD1, digital pin 20), muxed to peripheral A.
+    /// There's no TWI driver in this crate yet, so this is a pin, not a
+    /// configured `Wire` peripheral.
+    pub wire_sda: Pb12<PfA>,
+    /// `Wire`'s SCL line (TWCK1, digital pin 21), muxed to peripheral A.
+    pub wire_scl: Pb13<PfA>,
+    /// `Wire1`'s SDA line (TWD0), muxed to peripheral A. There's no TWI
+    /// driver in this crate yet, so this is a pin, not a configured `Wire1`
+    /// peripheral.
+    pub wire1_sda: Pa17<PfA>,
+    /// `Wire1`'s SCL line (TWCK0), muxed to peripheral A.
+    pub wire1_scl: Pa18<PfA>,
+
+    /// SPI0's MISO line, muxed to peripheral A. There's no SPI driver in
+    /// this crate yet, so this is a pin, not a configured SPI master.
+    pub spi_miso: Pa25<PfA>,
+    /// SPI0's MOSI line, muxed to peripheral A.
+    pub spi_mosi: Pa26<PfA>,
+    /// SPI0's SCLK line, muxed to peripheral A.
+    pub spi_sclk: Pa27<PfA>,
+    /// SPI0's NPCS0 (chip select 0) line, muxed to peripheral A.
+    pub spi_npcs0: Pa28<PfA>,
+
+    /// `usb_id`, muxed to peripheral A, for the Due's "Native" USB port.
+    /// There's no device-mode USB driver in this crate yet (see
+    /// `hal::uotghs::device`), so this is a pin, not a configured
+    /// `usb-device` `UsbBusAllocator`.
+    #[cfg(feature = "usb-device")]
+    pub usb_id: Pb11<PfA>,
+    /// `usb_vbof`, muxed to peripheral A, for the Due's "Native" USB port.
+    #[cfg(feature = "usb-device")]
+    pub usb_vbof: Pb10<PfA>,
+    // a0..a11 and a12_dac0/a13_dac1 are left out of `Board` entirely: the
+    // SAM3X ADC/DAC sample these pins directly without a PIO peripheral-
+    // function mux, and this crate has no ADC/DAC driver to hand back an
+    // `analog_read`/`analog_write` helper on top of, so there's nothing
+    // useful to pre-configure. Reach them through `Pins` directly until
+    // this crate grows an ADC/DAC module.
+}
+
+impl Board {
+    /// Consume the raw [`Pins`] and the U(S)ART peripherals, muxing pins to
+    /// their fixed peripheral function and constructing the Due's four
+    /// serial ports.
+    #[allow(unused_variables)]
+    pub fn new(
+        pins: Pins,
+        uart: pac::UART,
+        usart0: pac::USART0,
+        usart1: pac::USART1,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))] usart3: pac::USART3,
+    ) -> Self {
+        let serial0 = Uart0::new(
+            uart,
+            pins.d0_rx0.into_peripheral_a(),
+            pins.d1_tx0.into_peripheral_a(),
+        );
+        let serial1 = Usart0::new(
+            usart0,
+            pins.d19_rx1.into_peripheral_a(),
+            pins.d18_tx1.into_peripheral_a(),
+        );
+        let serial2 = Usart1::new(
+            usart1,
+            pins.d17_rx2.into_peripheral_a(),
+            pins.d16_tx2.into_peripheral_a(),
+        );
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        let serial3 = Usart3::new(
+            usart3,
+            pins.d15_rx3.into_peripheral_b(),
+            pins.d14_tx3.into_peripheral_b(),
+        );
+
+        Self {
+            serial0,
+            serial1,
+            serial2,
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            serial3,
+            wire_sda: pins.d20_sda0_twd1.into_peripheral_a(),
+            wire_scl: pins.d21_scl0_twck1.into_peripheral_a(),
+            wire1_sda: pins.sda1_twd0.into_peripheral_a(),
+            wire1_scl: pins.scl1_twck0.into_peripheral_a(),
+            spi_miso: pins.miso.into_peripheral_a(),
+            spi_mosi: pins.mosi.into_peripheral_a(),
+            spi_sclk: pins.sclk.into_peripheral_a(),
+            spi_npcs0: pins.npcs0.into_peripheral_a(),
+            #[cfg(feature = "usb-device")]
+            usb_id: pins.usb_id.into_peripheral_a(),
+            #[cfg(feature = "usb-device")]
+            usb_vbof: pins.usb_vbof.into_peripheral_a(),
+        }
+    }
+}
@@ -14,8 +14,21 @@ pub use cortex_m_rt::entry;
 #[cfg(feature = "panic_halt")]
 pub extern crate panic_halt;
 
+/// Re-exports [`defmt_rtt`] as the `defmt` global logger, transported over
+/// RTT, and [`panic_probe`] (with its `print-defmt` feature) as the panic
+/// handler, mirroring the `#[cfg(feature = "panic_halt")]` re-export above.
+/// `build.rs` links in `defmt.x` automatically whenever this feature is on.
+#[cfg(feature = "defmt-rtt")]
+pub extern crate defmt_rtt;
+
+#[cfg(feature = "defmt-rtt")]
+pub extern crate panic_probe;
+
 use hal::define_pins;
 
+mod board;
+pub use board::Board;
+
 // The docs could be further improved with details of the specific channels etc
 define_pins!(
     /// Maps the pins to their arduino names and the numbers printed on the board.
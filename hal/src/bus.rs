@@ -2,6 +2,148 @@
 use crate::target_device;
 use target_device::MATRIX;
 
+/// AHB matrix masters that can be granted access to a slave region.
+///
+/// Best-effort mapping of the SAM3X/A matrix's fixed master IDs; consult the
+/// datasheet's "Bus Matrix Masters" table for the target part if in doubt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MasterId {
+    /// Cortex-M3 core, instruction and data buses.
+    CortexM = 0,
+    /// Peripheral DMA Controller channels (UART/USART, HSMCI, TC, SSC, ...).
+    Pdc = 1,
+    /// Central DMA Controller (DMAC peripheral).
+    Dmac = 2,
+    /// USB high-speed device/host controller (UOTGHS) DMA.
+    Uotghs = 3,
+    /// Ethernet MAC (EMAC) DMA.
+    #[cfg(feature = "sam3x")]
+    Emac = 4,
+}
+
+/// AHB matrix slave regions that masters arbitrate for access to.
+///
+/// Best-effort mapping of the SAM3X/A matrix's fixed slave IDs; consult the
+/// datasheet's "Bus Matrix Slaves" table for the target part if in doubt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SlaveId {
+    /// Internal SRAM.
+    InternalSram = 0,
+    /// Internal ROM.
+    InternalRom = 1,
+    /// External Bus Interface, chip select 0.
+    Ebi0 = 2,
+    /// External Bus Interface, chip select 1 (SDRAM, when routed there via
+    /// `BusInterconnect::enable_sdram_chip_select`).
+    Ebi1 = 3,
+    /// External Bus Interface, chip select 2.
+    Ebi2 = 4,
+    /// External Bus Interface, chip select 3 (NAND flash, when enabled via
+    /// `BusInterconnect::enable_nandflash_logic`).
+    Ebi3 = 5,
+    /// USB high-speed controller (UOTGHS) dual-port RAM.
+    Uotghs = 6,
+    /// Internal peripheral bridge (APB).
+    InternalPeripherals = 7,
+}
+
+/// Arbitration scheme a matrix slave uses when more than one master
+/// requests it on the same cycle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArbitrationType {
+    /// Masters are granted access in round-robin order.
+    RoundRobin,
+    /// Masters are granted access according to their configured fixed
+    /// priority, set via `SlaveConfigBuilder::master_priority`.
+    FixedPriority,
+}
+
+/// Which master a slave defaults to granting bus ownership to when no
+/// master is currently requesting it, avoiding the one-cycle arbitration
+/// delay the next request would otherwise pay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefaultMaster {
+    /// No default master; every access pays the arbitration delay.
+    None,
+    /// Default to whichever master last accessed the slave.
+    LastAccess,
+    /// Always default to a fixed master.
+    Fixed(MasterId),
+}
+
+/// Builder for one matrix slave's arbitration type, default master, and
+/// per-master fixed priorities, following the same
+/// `Builder`/terminal-method pattern as `crate::watchdog::WdtBuilder`.
+pub struct SlaveConfigBuilder<'a> {
+    matrix: &'a mut MATRIX,
+    slave: SlaveId,
+    arbitration: ArbitrationType,
+    default_master: DefaultMaster,
+    priorities: [u8; 8],
+}
+
+impl<'a> SlaveConfigBuilder<'a> {
+    /// Select the slave's arbitration scheme. Defaults to round-robin.
+    pub fn arbitration(mut self, arbitration: ArbitrationType) -> Self {
+        self.arbitration = arbitration;
+        self
+    }
+
+    /// Select the slave's default master. Defaults to `DefaultMaster::None`.
+    pub fn default_master(mut self, default_master: DefaultMaster) -> Self {
+        self.default_master = default_master;
+        self
+    }
+
+    /// Set `master`'s fixed priority (0-3, higher wins) for this slave, used
+    /// when `ArbitrationType::FixedPriority` is selected.
+    pub fn master_priority(mut self, master: MasterId, priority: u8) -> Self {
+        self.priorities[master as usize] = priority.min(3);
+        self
+    }
+
+    /// Apply the configuration to the slave's `SCFG`, `PRAS`, and `PRBS`
+    /// registers.
+    pub fn apply(self) {
+        let slave = self.slave as usize;
+
+        self.matrix.scfg[slave].modify(|_, w| unsafe {
+            let w = match self.arbitration {
+                ArbitrationType::RoundRobin => w.arbt().clear_bit(),
+                ArbitrationType::FixedPriority => w.arbt().set_bit(),
+            };
+            match self.default_master {
+                DefaultMaster::None => w.defmstr_type().bits(0),
+                DefaultMaster::LastAccess => w.defmstr_type().bits(1),
+                DefaultMaster::Fixed(master) => {
+                    w.defmstr_type().bits(2).fixed_defmstr().bits(master as u8)
+                }
+            }
+        });
+
+        self.matrix.pras[slave].write(|w| unsafe {
+            w.m0pr()
+                .bits(self.priorities[0])
+                .m1pr()
+                .bits(self.priorities[1])
+                .m2pr()
+                .bits(self.priorities[2])
+                .m3pr()
+                .bits(self.priorities[3])
+        });
+        self.matrix.prbs[slave].write(|w| unsafe {
+            w.m4pr()
+                .bits(self.priorities[4])
+                .m5pr()
+                .bits(self.priorities[5])
+                .m6pr()
+                .bits(self.priorities[6])
+                .m7pr()
+                .bits(self.priorities[7])
+        });
+    }
+}
+
 /// Bus interconnect configuration register block.
 pub struct BusInterconnect {
     matrix: MATRIX,
@@ -43,6 +185,39 @@ impl BusInterconnect {
     pub fn enable_sysio(&self) {
         self.ccfg_sysio.modify(|_, w| w.sysio12().set_bit());
     }
+
+    /// Start configuring `slave`'s arbitration type, default master, and
+    /// per-master fixed priorities. Nothing is written to the hardware until
+    /// `SlaveConfigBuilder::apply` is called.
+    pub fn configure_slave(&mut self, slave: SlaveId) -> SlaveConfigBuilder<'_> {
+        SlaveConfigBuilder {
+            matrix: &mut self.matrix,
+            slave,
+            arbitration: ArbitrationType::RoundRobin,
+            default_master: DefaultMaster::None,
+            priorities: [0; 8],
+        }
+    }
+
+    /// Route the SDRAM Controller (rather than the Static Memory
+    /// Controller) onto the `NCS1` chip select. Required before
+    /// `crate::sdramc::SdramcBuilder::init` can talk to an external SDRAM
+    /// chip, since both controllers share that pin electrically.
+    #[cfg(feature = "sam3x8h")]
+    pub fn enable_sdram_chip_select(&mut self) {
+        self.ccfg_smcnfcs.modify(|_, w| w.sdramen().set_bit());
+    }
+
+    /// Enable NAND flash logic (extra address cycles, ready/busy handling)
+    /// on Static Memory Controller chip select `cs` (0-3).
+    pub fn enable_nandflash_logic(&mut self, cs: u8) {
+        match cs {
+            0 => self.ccfg_smcnfcs.modify(|_, w| w.smc_nfcs0().set_bit()),
+            1 => self.ccfg_smcnfcs.modify(|_, w| w.smc_nfcs1().set_bit()),
+            2 => self.ccfg_smcnfcs.modify(|_, w| w.smc_nfcs2().set_bit()),
+            _ => self.ccfg_smcnfcs.modify(|_, w| w.smc_nfcs3().set_bit()),
+        }
+    }
 }
 
 impl From<MATRIX> for BusInterconnect {
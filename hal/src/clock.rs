@@ -18,6 +18,11 @@ pub type FastRCFreq = target_device::pmc::ckgr_mor::MOSCRCF_A;
 pub type ClockSource = target_device::pmc::pmc_mckr::CSS_A;
 /// Valid prescaler values for the system master clock
 pub type ClockPrescaler = target_device::pmc::pmc_mckr::PRES_A;
+/// Valid clock sources for a programmable clock output (PCK0-PCK2). Unlike
+/// [`ClockSource`], which selects what feeds the master clock, a PCK can
+/// also be fed directly from the already-divided master clock, so this is a
+/// distinct hardware field with its own enum.
+pub type PckSource = target_device::pmc::pmc_pck::CSS_A;
 
 /// Oscillator sources that can be used by the slow clock.
 ///
@@ -31,6 +36,10 @@ pub enum SlowClockSource {
     /// Slow clock external crystal oscillator, runs at 32768Hz and
     /// more accurate
     LowPowerXtal32Khz,
+    /// A clean external square-wave clock fed directly into the crystal
+    /// oscillator pin, bypassing the crystal oscillator circuit. Runs at
+    /// 32768Hz, same as `LowPowerXtal32Khz`.
+    ExternalClock,
 }
 
 /// Oscillator sources that can be used by the main clock.
@@ -52,6 +61,11 @@ pub enum MainClockSource {
     FastRc(FastRCFreq),
     /// External Crystal or Ceramic oscillator
     MainXtal,
+    /// A clean external square-wave clock fed directly into the crystal
+    /// oscillator pin, bypassing the crystal oscillator circuit. Useful for
+    /// boards that feed in an accurate clock from an external oscillator
+    /// module instead of a physical crystal.
+    ExternalClock,
 }
 
 /// Divider to apply to the master clock when using either PLLA or UPLL as
@@ -63,6 +77,17 @@ pub enum PllDiv {
     Two = 1,
 }
 
+/// Identifies one of the PMC's three programmable clock outputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PckId {
+    /// Programmable clock output 0
+    Pck0,
+    /// Programmable clock output 1
+    Pck1,
+    /// Programmable clock output 2
+    Pck2,
+}
+
 /// Configuration options for setting up the PLLA clock source.  The output
 /// frequency is the source clock frequency * (mula + 1)/diva.  The clock is
 /// disabled when mula = 0.
@@ -75,6 +100,290 @@ pub struct PllAClockConfig {
     pub count: u8,
 }
 
+/// One past the highest peripheral ID (`CAN1`, 44) used to control clock
+/// gating, sized to index `SystemClocks::peripheral_refcounts` directly by
+/// `PeripheralID as usize`.
+const PERIPHERAL_COUNT: usize = 45;
+
+/// Minimum output frequency accepted by the PLLA voltage-controlled
+/// oscillator, per the datasheet.
+const PLLA_VCO_MIN: u32 = 80_000_000;
+/// Maximum output frequency accepted by the PLLA voltage-controlled
+/// oscillator, per the datasheet.
+const PLLA_VCO_MAX: u32 = 240_000_000;
+/// Maximum supported master clock frequency.
+const MCK_MAX: u32 = 84_000_000;
+
+/// Errors from building a [`ClockConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockError {
+    /// No `(mula, diva)` pair brings PLLA's output, for the selected main
+    /// clock source, within range of the requested master clock frequency.
+    UnreachableTarget,
+}
+
+/// The `(mula, diva)` pair chosen by [`ClockConfig::freeze`], along with the
+/// resulting PLLA output frequency and whether `plladiv2` is required to
+/// bring it down towards the target master clock frequency.
+struct PllaSolution {
+    config: PllAClockConfig,
+    plla_freq: Hertz,
+    pll_div2: bool,
+}
+
+/// Search the space of valid `(mula, diva)` pairs for the one that makes
+/// PLLA's output frequency the closest approximation of `target`, optionally
+/// pre-doubling the target to make use of `plladiv2` when that lands inside
+/// the PLLA VCO range better than running undivided.
+fn solve_plla(mainck: Hertz, target: Hertz) -> Option<PllaSolution> {
+    let mainck = mainck.0;
+    // (error, mula, diva, pll_div2)
+    let mut best: Option<(u32, u16, u8, bool)> = None;
+
+    for pll_div2 in [false, true] {
+        let plla_target = if pll_div2 {
+            target.0.saturating_mul(2)
+        } else {
+            target.0
+        };
+
+        for diva in 1..=255u32 {
+            // mula = round(plla_target * diva / mainck) - 1
+            let numer = u64::from(plla_target) * u64::from(diva);
+            let mula_plus_one = ((numer + u64::from(mainck) / 2) / u64::from(mainck)) as u32;
+            if mula_plus_one == 0 || mula_plus_one > 2048 {
+                continue;
+            }
+
+            let plla_out = (u64::from(mainck) * u64::from(mula_plus_one) / u64::from(diva)) as u32;
+            if plla_out < PLLA_VCO_MIN || plla_out > PLLA_VCO_MAX {
+                continue;
+            }
+
+            let err = plla_out.abs_diff(plla_target);
+            if best.map_or(true, |(best_err, ..)| err < best_err) {
+                best = Some((err, (mula_plus_one - 1) as u16, diva as u8, pll_div2));
+            }
+        }
+    }
+
+    let (_, mula, diva, pll_div2) = best?;
+    let plla_freq = Hertz((u64::from(mainck) * (u64::from(mula) + 1) / u64::from(diva)) as u32);
+    Some(PllaSolution {
+        config: PllAClockConfig {
+            mula,
+            diva,
+            count: 0x3f,
+        },
+        plla_freq,
+        pll_div2,
+    })
+}
+
+/// Pick the smallest master clock prescaler ( one of `/1`, `/2`, `/4`, ...,
+/// `/64`) that brings `freq` down to at or under `MCK_MAX`.
+fn prescaler_for(freq: Hertz) -> ClockPrescaler {
+    let mut shift = 0u32;
+    while (freq.0 >> shift) > MCK_MAX && shift < 6 {
+        shift += 1;
+    }
+    match shift {
+        0 => ClockPrescaler::CLK_1,
+        1 => ClockPrescaler::CLK_2,
+        2 => ClockPrescaler::CLK_4,
+        3 => ClockPrescaler::CLK_8,
+        4 => ClockPrescaler::CLK_16,
+        5 => ClockPrescaler::CLK_32,
+        _ => ClockPrescaler::CLK_64,
+    }
+}
+
+/// Builder for configuring the system clocks to reach a target master clock
+/// frequency, following the `Config`/`freeze()` pattern used by the stm32f3
+/// and stm32f7 HALs.
+///
+/// Unlike [`SystemClocks::with_plla_clk`], which hardcodes the PLLA
+/// multiplier/divider to reach 84MHz, `ClockConfig` searches for the
+/// `(mula, diva)` pair that best approximates a caller-supplied target
+/// frequency and derives the master clock prescaler needed to keep the
+/// result within the device's limits.
+pub struct ClockConfig {
+    pmc: PMC,
+    supc: SUPC,
+    main_clock_source: MainClockSource,
+    target_mck: Option<Hertz>,
+    plla_override: Option<PllAClockConfig>,
+    plla_div2: bool,
+    mck_prescaler: Option<ClockPrescaler>,
+}
+
+impl ClockConfig {
+    /// Start building a clock configuration. Defaults to running the main
+    /// clock off the external crystal, same as [`SystemClocks::with_plla_clk`].
+    pub fn new(pmc: PMC, supc: SUPC) -> Self {
+        Self {
+            pmc,
+            supc,
+            main_clock_source: MainClockSource::MainXtal,
+            target_mck: None,
+            plla_override: None,
+            plla_div2: false,
+            mck_prescaler: None,
+        }
+    }
+
+    /// Select the oscillator source for the main clock that PLLA is derived
+    /// from.
+    pub fn main_clock_source(mut self, source: MainClockSource) -> Self {
+        self.main_clock_source = source;
+        self
+    }
+
+    /// Request that the master clock run as close as possible to `freq`.
+    /// Defaults to 84MHz if not set. Ignored if an explicit PLLA
+    /// multiplier/divider has been set via [`Self::plla`].
+    pub fn sysclk(mut self, freq: Hertz) -> Self {
+        self.target_mck = Some(freq);
+        self
+    }
+
+    /// Configure PLLA with an explicit multiplier/divider pair instead of
+    /// searching for one that approximates [`Self::sysclk`]'s target. Useful
+    /// when a specific PLLA output frequency is needed for another
+    /// downstream clock (e.g. a programmable clock output), not just the
+    /// master clock.
+    pub fn plla(mut self, config: PllAClockConfig) -> Self {
+        self.plla_override = Some(config);
+        self
+    }
+
+    /// Whether the master clock should additionally be divided by 2 after
+    /// PLLA, when PLLA itself is configured via [`Self::plla`]. Has no
+    /// effect when PLLA is auto-configured from [`Self::sysclk`], since the
+    /// solver already picks whichever divider best approximates the target.
+    pub fn plla_div2(mut self, div2: bool) -> Self {
+        self.plla_div2 = div2;
+        self
+    }
+
+    /// Use this exact master clock prescaler instead of the smallest one
+    /// that keeps the result at or under the device's maximum supported
+    /// frequency.
+    pub fn mck_prescaler(mut self, prescaler: ClockPrescaler) -> Self {
+        self.mck_prescaler = Some(prescaler);
+        self
+    }
+
+    /// Apply the configuration: select the main clock source, apply PLLA's
+    /// multiplier/divider (explicit, or best-fit to the requested target),
+    /// and select the master clock prescaler needed to keep the result
+    /// within limits. Waits on the relevant `PMC_SR` ready flags
+    /// (`MOSCXTS`/`MOSCSELS`, `LOCKA`, `MCKRDY`) at each step, and raises or
+    /// lowers the flash wait states around the switch, all via the same
+    /// primitives `SystemClocks`'s other constructors use.
+    ///
+    /// Returns the resulting `SystemClocks` along with a frozen `Clocks`
+    /// snapshot of the frequencies that were achieved, so callers don't have
+    /// to assume the 84MHz magic number.
+    ///
+    /// Returns [`ClockError::UnreachableTarget`] if the requested
+    /// [`Self::sysclk`] target can't be approximated by any valid PLLA
+    /// `(mula, diva)` pair for the selected main clock source. By then the
+    /// main clock source has already been switched (its rate is read back
+    /// from the hardware to solve for PLLA), but PLLA and the master clock
+    /// are left untouched. An explicit [`Self::plla`] override is applied
+    /// as given and never produces this error.
+    pub fn freeze(self) -> Result<(SystemClocks, Clocks), ClockError> {
+        let mut clk = SystemClocks::from_parts(self.pmc, self.supc);
+        clk.set_main_clock_source(self.main_clock_source);
+        let mainck = clk.get_main_clock_rate();
+
+        let (plla_config, plla_freq, pll_div2) = match self.plla_override {
+            Some(config) => {
+                let plla_freq = Hertz(
+                    (u64::from(mainck.0) * (u64::from(config.mula) + 1) / u64::from(config.diva))
+                        as u32,
+                );
+                (config, plla_freq, self.plla_div2)
+            }
+            None => {
+                let target = self.target_mck.unwrap_or_else(|| MegaHertz(84).into());
+                let solution = solve_plla(mainck, target).ok_or(ClockError::UnreachableTarget)?;
+                (solution.config, solution.plla_freq, solution.pll_div2)
+            }
+        };
+
+        clk.configure_plla(plla_config);
+
+        let pll_div2_shift = if pll_div2 { 1 } else { 0 };
+        let predivide = Hertz(plla_freq.0 >> pll_div2_shift);
+        let prescaler = self
+            .mck_prescaler
+            .unwrap_or_else(|| prescaler_for(predivide));
+
+        clk.set_master_clock_source_and_prescaler(ClockSource::PLLA_CLK, Some(prescaler), pll_div2);
+
+        let clocks = clk.clocks();
+        Ok((clk, clocks))
+    }
+}
+
+/// A `Copy` snapshot of the frequencies `SystemClocks` was configured with.
+///
+/// `get_syscore` and friends require `&mut SystemClocks` and recompute their
+/// result from registers on every call, which makes them awkward to hand to
+/// peripheral driver setup functions. `Clocks` instead captures every clock
+/// domain once, at configuration time, so it can be passed by value to
+/// compute baud rates, timer dividers, and the like - and its existence acts
+/// as compile-time proof that the clocks have been configured, matching this
+/// module's stated goal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clocks {
+    /// Frequency of the slow clock.
+    pub slow_clk: Hertz,
+    /// Frequency of the main clock.
+    pub main_clk: Hertz,
+    /// Frequency of the PLLA clock.
+    pub plla_clk: Hertz,
+    /// Frequency of the UPLL clock.
+    pub upll_clk: Hertz,
+    /// Frequency of the master clock.
+    pub master_clk: Hertz,
+}
+
+impl Clocks {
+    /// Frequency of the slow clock.
+    pub fn slck(&self) -> Hertz {
+        self.slow_clk
+    }
+
+    /// Frequency of the main clock.
+    pub fn main_clk(&self) -> Hertz {
+        self.main_clk
+    }
+
+    /// Frequency of the PLLA clock.
+    pub fn plla_clk(&self) -> Hertz {
+        self.plla_clk
+    }
+
+    /// Frequency of the UPLL clock.
+    pub fn upll_clk(&self) -> Hertz {
+        self.upll_clk
+    }
+
+    /// Frequency of the master clock (MCK).
+    pub fn mck(&self) -> Hertz {
+        self.master_clk
+    }
+
+    /// Frequency the Cortex-M3 core runs at. On this device the processor
+    /// clock is the master clock undivided.
+    pub fn sysclk(&self) -> Hertz {
+        self.master_clk
+    }
+}
+
 /// `SystemClocks` encapsulates the PMC and SUPC clock hardware.
 /// It provides a type safe way to configure the system clocks.
 /// Initializing the `SystemClocks` instance configures the system to run at
@@ -85,6 +394,11 @@ pub struct SystemClocks {
     pub pmc: PMC,
     /// Power Supply Controller
     pub supc: SUPC,
+    /// Per-peripheral enable refcount, indexed by `PeripheralID as usize`, so
+    /// that two drivers sharing a peripheral clock (e.g. two channels of the
+    /// same TC block) don't have one disable the clock out from under the
+    /// other.
+    peripheral_refcounts: [u8; PERIPHERAL_COUNT],
 }
 
 impl core::ops::Deref for SystemClocks {
@@ -102,10 +416,20 @@ impl core::ops::DerefMut for SystemClocks {
 }
 
 impl SystemClocks {
+    /// Construct a `SystemClocks` from its constituent peripherals with a
+    /// freshly-zeroed peripheral clock refcount table.
+    fn from_parts(pmc: PMC, supc: SUPC) -> Self {
+        Self {
+            pmc,
+            supc,
+            peripheral_refcounts: [0; PERIPHERAL_COUNT],
+        }
+    }
+
     /// Select the specified slow clock oscillator, and clock the system to run
     /// at that frequency.
     pub fn with_slow_clk(pmc: PMC, supc: SUPC, use_external_crystal: bool) -> Self {
-        let mut clk = Self { pmc, supc };
+        let mut clk = Self::from_parts(pmc, supc);
         if use_external_crystal {
             clk.enable_slow_clock_xtal();
         }
@@ -115,7 +439,7 @@ impl SystemClocks {
 
     /// Set the main clock source, and clock the system to run at that frequency.
     pub fn with_main_clk(pmc: PMC, supc: SUPC, source: MainClockSource) -> Self {
-        let mut clk = Self { pmc, supc };
+        let mut clk = Self::from_parts(pmc, supc);
         clk.set_main_clock_source(source);
         clk.set_master_clock_source_and_prescaler(ClockSource::MAIN_CLK, None, false);
 
@@ -233,6 +557,19 @@ impl SystemClocks {
         }
     }
 
+    /// Capture a `Copy` snapshot of every clock domain's current frequency,
+    /// suitable for handing to peripheral setup functions that only need to
+    /// read the configured frequencies rather than reconfigure them.
+    pub fn clocks(&mut self) -> Clocks {
+        Clocks {
+            slow_clk: self.get_slow_clock_rate(),
+            main_clk: self.get_main_clock_rate(),
+            plla_clk: self.get_plla_clock_rate(),
+            upll_clk: self.get_upll_clock_rate(),
+            master_clk: self.get_syscore(),
+        }
+    }
+
     /// Slow clock is always enabled, but is sourced from a low-accuracy RC
     /// oscillator.  This enables the more accurate crystal oscillator and
     /// switch to use that as the slow clock source.  Once the crystal
@@ -244,6 +581,21 @@ impl SystemClocks {
             .write_with_zero(|w| w.key().passwd().xtalsel().set_bit());
     }
 
+    /// Select the oscillator source for the slow clock. Slow clock is always
+    /// enabled, starting out sourced from a low-accuracy RC oscillator, but
+    /// once switched to the crystal oscillator (or an external clock fed
+    /// into the same pin) it cannot be switched back.
+    pub fn set_slow_clock_source(&mut self, source: SlowClockSource) {
+        match source {
+            SlowClockSource::LowPowerRC => (),
+            SlowClockSource::LowPowerXtal32Khz => self.enable_slow_clock_xtal(),
+            SlowClockSource::ExternalClock => {
+                self.supc.mr.modify(|_, w| w.oscbypass().set_bit());
+                self.enable_slow_clock_xtal();
+            }
+        }
+    }
+
     /// Disabling the main clock is usually only done to enter low power/idle
     /// states.  It may only be re-enabled by an interrupt or rebooting.
     pub fn disable_main_clock(&mut self) {
@@ -276,6 +628,13 @@ impl SystemClocks {
         // startup cycles = 8 * startup_time / SLCK
         let startup_time: u8 = 8;
 
+        // A bypassed oscillator is driven directly by a clean external
+        // square wave, so it must not be combined with moscxten, and has no
+        // startup delay to wait out.
+        let bypass = matches!(source, MainClockSource::ExternalClock);
+        self.ckgr_mor
+            .modify(|_, w| w.key().passwd().moscxtby().bit(bypass));
+
         // To ensure a smooth transition in case other clocks are running off
         // main clock, we enable both clocks, wait out the startup time,
         // switch to the desired clock, then disable the unused clock
@@ -285,14 +644,17 @@ impl SystemClocks {
                 .moscrcen()
                 .set_bit()
                 .moscxten()
-                .set_bit()
+                .bit(!bypass)
                 .moscxtst()
                 .bits(startup_time)
         });
         // Wait until RC startup time runs out
         while !self.pmc_sr.read().moscrcs().bits() {}
-        // Wait until Xtal startup time runs out
-        while !self.pmc_sr.read().moscxts().bits() {}
+        // Wait until Xtal startup time runs out, unless bypassing the
+        // crystal oscillator circuit entirely
+        if !bypass {
+            while !self.pmc_sr.read().moscxts().bits() {}
+        }
 
         match source {
             MainClockSource::FastRc(f) => {
@@ -313,7 +675,7 @@ impl SystemClocks {
                 self.ckgr_mor
                     .modify(|_, w| w.key().passwd().moscxten().clear_bit());
             }
-            MainClockSource::MainXtal => {
+            MainClockSource::MainXtal | MainClockSource::ExternalClock => {
                 self.ckgr_mor
                     .modify(|_, w| w.key().passwd().moscsel().set_bit());
                 // Wait until oscillator selection reports ready
@@ -330,6 +692,7 @@ impl SystemClocks {
     /// Return the currently-active main clock source.
     pub fn get_main_clock_source(&self) -> MainClockSource {
         match self.ckgr_mor.read().moscsel().bits() {
+            true if self.ckgr_mor.read().moscxtby().bits() => MainClockSource::ExternalClock,
             true => MainClockSource::MainXtal,
             false => match self.ckgr_mor.read().moscrcf().variant() {
                 Variant::Val(s) => MainClockSource::FastRc(s),
@@ -389,6 +752,67 @@ impl SystemClocks {
         self.ckgr_uckr.modify(|_, w| w.upllen().clear_bit());
     }
 
+    /// Configure and enable a programmable clock output (PCK0-PCK2),
+    /// deriving it from `source` divided by `2.pow(prescaler)` (`prescaler`
+    /// is clamped to `0..=7`, the width of the hardware field). These
+    /// outputs are commonly used to clock external sensors or codecs (e.g.
+    /// an off-chip DDS), or to export a reference clock off-chip.
+    ///
+    /// Spins until the PMC reports the output has stabilized, then returns
+    /// the resulting frequency so callers can verify the output rate.
+    pub fn enable_programmable_clock(
+        &mut self,
+        pck: PckId,
+        source: PckSource,
+        prescaler: u8,
+    ) -> Hertz {
+        let prescaler = prescaler.min(7);
+        let source_freq = match source {
+            PckSource::SLOW_CLK => self.get_slow_clock_rate(),
+            PckSource::MAIN_CLK => self.get_main_clock_rate(),
+            PckSource::PLLA_CLK => self.get_plla_clock_rate(),
+            PckSource::UPLL_CLK => self.get_upll_clock_rate(),
+            PckSource::MCK => self.get_syscore(),
+        };
+
+        match pck {
+            PckId::Pck0 => self
+                .pmc_pck0
+                .write(|w| unsafe { w.css().variant(source).pres().bits(prescaler) }),
+            PckId::Pck1 => self
+                .pmc_pck1
+                .write(|w| unsafe { w.css().variant(source).pres().bits(prescaler) }),
+            PckId::Pck2 => self
+                .pmc_pck2
+                .write(|w| unsafe { w.css().variant(source).pres().bits(prescaler) }),
+        }
+
+        match pck {
+            PckId::Pck0 => self.pmc_scer.write_with_zero(|w| w.pck0().set_bit()),
+            PckId::Pck1 => self.pmc_scer.write_with_zero(|w| w.pck1().set_bit()),
+            PckId::Pck2 => self.pmc_scer.write_with_zero(|w| w.pck2().set_bit()),
+        }
+
+        // Wait for the output to report stable
+        // 0 = not ready, 1 = ready
+        match pck {
+            PckId::Pck0 => while self.pmc_sr.read().pckrdy0().bit_is_clear() {},
+            PckId::Pck1 => while self.pmc_sr.read().pckrdy1().bit_is_clear() {},
+            PckId::Pck2 => while self.pmc_sr.read().pckrdy2().bit_is_clear() {},
+        }
+
+        Hertz(source_freq.0 >> prescaler)
+    }
+
+    /// Disable a previously-enabled programmable clock output.
+    pub fn disable_programmable_clock(&mut self, pck: PckId) {
+        match pck {
+            PckId::Pck0 => self.pmc_scdr.write_with_zero(|w| w.pck0().set_bit()),
+            PckId::Pck1 => self.pmc_scdr.write_with_zero(|w| w.pck1().set_bit()),
+            PckId::Pck2 => self.pmc_scdr.write_with_zero(|w| w.pck2().set_bit()),
+        }
+    }
+
     /// Select which clock source the master clock should use, along with some
     /// options for dividing the source clock.
     pub fn set_master_clock_source_and_prescaler(
@@ -397,6 +821,15 @@ impl SystemClocks {
         prescaler: Option<ClockPrescaler>,
         pll_div2: bool,
     ) {
+        let current_freq = self.get_syscore();
+        let projected_freq = self.projected_syscore(source, prescaler, pll_div2);
+
+        // Running the core faster than flash access time allows will fault,
+        // so raise the wait states before switching to a faster clock.
+        if projected_freq.0 > current_freq.0 {
+            self.set_flash_wait_states(projected_freq);
+        }
+
         // For PLLs, prescaler should be applied before changing the clock source
         if source == ClockSource::PLLA_CLK || source == ClockSource::UPLL_CLK {
             if let Some(prescaler) = prescaler {
@@ -447,12 +880,107 @@ impl SystemClocks {
                 while !self.pmc_sr.read().mckrdy().bits() {}
             }
         }
+
+        // It's safe to drop down to fewer wait states only once the core is
+        // confirmed to be running at (or below) the new, slower frequency.
+        if projected_freq.0 < current_freq.0 {
+            self.set_flash_wait_states(projected_freq);
+        }
     }
 
-    /// Enable the clock for the specified peripheral.  Some peripherals'
-    /// clocks are not under PMC control - passing the ID for these clocks
-    /// will silently do nothing.
+    /// Compute what `get_syscore` would return if the master clock were
+    /// switched to `source`/`prescaler`/`pll_div2`, without actually
+    /// touching any registers. Used to decide flash wait states ahead of an
+    /// actual clock switch.
+    fn projected_syscore(
+        &self,
+        source: ClockSource,
+        prescaler: Option<ClockPrescaler>,
+        pll_div2: bool,
+    ) -> Hertz {
+        let mut freq = match source {
+            ClockSource::SLOW_CLK => self.get_slow_clock_rate(),
+            ClockSource::MAIN_CLK => self.get_main_clock_rate(),
+            ClockSource::PLLA_CLK => self.get_plla_clock_rate(),
+            ClockSource::UPLL_CLK => self.get_upll_clock_rate(),
+        };
+
+        if (source == ClockSource::PLLA_CLK || source == ClockSource::UPLL_CLK) && pll_div2 {
+            freq.0 /= 2;
+        }
+
+        let pres = prescaler.unwrap_or_else(|| self.pmc_mckr.read().pres().variant());
+        freq.0 = match pres {
+            CLK_3 => freq.0 / 3,
+            x => freq.0 >> (x as u8),
+        };
+        freq
+    }
+
+    /// Program the EEFC0/EEFC1 flash wait states (`FMR.FWS`) for the given
+    /// core frequency, per the datasheet's flash access time thresholds.
+    /// Must be called with the higher of the current/target frequency
+    /// *before* actually raising the master clock, and with the lower of the
+    /// two *after* lowering it; `set_master_clock_source_and_prescaler` takes
+    /// care of this ordering automatically.
+    pub fn set_flash_wait_states(&mut self, freq: Hertz) {
+        let fws: u8 = match freq.0 {
+            f if f <= 20_000_000 => 0,
+            f if f <= 40_000_000 => 1,
+            f if f <= 60_000_000 => 2,
+            _ => 3,
+        };
+
+        unsafe {
+            (*target_device::EFC0::ptr())
+                .fmr
+                .modify(|_, w| w.fws().bits(fws));
+        }
+
+        // EFC1 only exists on the dual-bank 8-series parts; the single-plane
+        // 256 KB parts (sam3a4c/sam3x4c/sam3x4e) don't have a second flash
+        // controller to program, matching the gate `flash.rs` uses for
+        // `impl_efc_instance!(EFC1, ..)`.
+        #[cfg(any(feature = "sam3a8c", feature = "sam3x8c", feature = "sam3x8e", feature = "sam3x8h"))]
+        unsafe {
+            (*target_device::EFC1::ptr())
+                .fmr
+                .modify(|_, w| w.fws().bits(fws));
+        }
+    }
+
+    /// Increment the enable refcount for `pid`'s clock, and turn the clock
+    /// on via `force_enable_peripheral_clock` only on the 0→1 transition.
+    /// This is the API that peripheral drivers should use, so that two
+    /// drivers sharing a clock domain (e.g. two channels of the same TC
+    /// block) don't have one disable the clock out from under the other.
     pub fn enable_peripheral_clock(&mut self, pid: PeripheralID) {
+        let count = &mut self.peripheral_refcounts[pid as usize];
+        *count = count.saturating_add(1);
+        if *count == 1 {
+            self.force_enable_peripheral_clock(pid);
+        }
+    }
+
+    /// Decrement the enable refcount for `pid`'s clock, and turn the clock
+    /// off via `force_disable_peripheral_clock` only on the 1→0 transition.
+    /// This is the API that peripheral drivers should use; see
+    /// `enable_peripheral_clock`.
+    pub fn disable_peripheral_clock(&mut self, pid: PeripheralID) {
+        let count = &mut self.peripheral_refcounts[pid as usize];
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            self.force_disable_peripheral_clock(pid);
+        }
+    }
+
+    /// Unconditionally enable the clock for the specified peripheral,
+    /// bypassing the refcount. Some peripherals' clocks are not under PMC
+    /// control - passing the ID for these clocks will silently do nothing.
+    ///
+    /// Prefer `enable_peripheral_clock` unless you're intentionally
+    /// bypassing the refcount (e.g. low-power bring-down code).
+    pub fn force_enable_peripheral_clock(&mut self, pid: PeripheralID) {
         match pid {
             PeripheralID::PMC => (),  // Clock not under PMC control
             PeripheralID::EFC0 => (), // Clock not under PMC control
@@ -507,10 +1035,13 @@ impl SystemClocks {
         }
     }
 
-    /// Disable the clock for the specified peripheral.  Some peripherals'
-    /// clocks are not under PMC control - passing the ID for these clocks
-    /// will silently do nothing.
-    pub fn disable_peripheral_clock(&mut self, pid: PeripheralID) {
+    /// Unconditionally disable the clock for the specified peripheral,
+    /// bypassing the refcount. Some peripherals' clocks are not under PMC
+    /// control - passing the ID for these clocks will silently do nothing.
+    ///
+    /// Prefer `disable_peripheral_clock` unless you're intentionally
+    /// bypassing the refcount (e.g. low-power bring-down code).
+    pub fn force_disable_peripheral_clock(&mut self, pid: PeripheralID) {
         match pid {
             PeripheralID::PMC => (),  // Clock not under PMC control
             PeripheralID::EFC0 => (), // Clock not under PMC control
@@ -564,4 +1095,115 @@ impl SystemClocks {
             PeripheralID::CAN1 => self.pmc_pcdr1.write_with_zero(|w| w.pid44().set_bit()),
         }
     }
+
+    /// Query whether `pid`'s clock is currently running, by reading
+    /// `PMC_PCSR0`/`PMC_PCSR1` and decoding the bit for its peripheral ID.
+    /// Peripherals not under PMC control are always considered enabled.
+    pub fn is_enabled(&self, pid: PeripheralID) -> bool {
+        match pid {
+            PeripheralID::PMC | PeripheralID::EFC0 | PeripheralID::EFC1 => true,
+            _ => {
+                let id = pid as u8;
+                if id < 32 {
+                    self.pmc_pcsr0.read().bits() & (1 << id) != 0
+                } else {
+                    self.pmc_pcsr1.read().bits() & (1 << (id - 32)) != 0
+                }
+            }
+        }
+    }
+
+    /// Iterate every `PeripheralID` whose clock is currently running. This
+    /// lets diagnostics and low-power bring-down code discover active clocks
+    /// at runtime without tracking the state separately.
+    pub fn enabled_peripherals(&self) -> impl Iterator<Item = PeripheralID> + '_ {
+        (0..PERIPHERAL_COUNT as u8)
+            .filter_map(peripheral_for_pid)
+            .filter(move |&pid| self.is_enabled(pid))
+    }
+
+    /// Disable every currently-enabled peripheral clock except those listed
+    /// in `keep`, bypassing the refcount. Useful immediately before entering
+    /// a low-power mode.
+    pub fn disable_all_except(&mut self, keep: &[PeripheralID]) {
+        for pid_num in 0..PERIPHERAL_COUNT as u8 {
+            if let Some(pid) = peripheral_for_pid(pid_num) {
+                if self.is_enabled(pid) && !keep.contains(&pid) {
+                    self.force_disable_peripheral_clock(pid);
+                    self.peripheral_refcounts[pid as usize] = 0;
+                }
+            }
+        }
+    }
+
+    /// Return the clock frequency fed to peripheral `pid`, so that driver
+    /// setup code (SPI, USART, TC, PWM, ADC, ...) can derive baud rates and
+    /// timer dividers from a real frequency value instead of a magic
+    /// constant. Unlike the APB1/APB2-prescaled peripheral buses on parts
+    /// like the stm32f4, the SAM3X clocks every peripheral directly from the
+    /// undivided master clock, so this is `get_syscore` under a
+    /// peripheral-oriented name; it exists so callers don't have to assume
+    /// that hardware detail, and so the clock tree can grow per-peripheral
+    /// dividers later without changing this signature.
+    pub fn peripheral_clock(&mut self, _pid: PeripheralID) -> Hertz {
+        self.get_syscore()
+    }
+}
+
+/// Map a raw PID number back to the `PeripheralID` variant whose clock it
+/// gates, mirroring the IDs handled by
+/// `force_enable_peripheral_clock`/`force_disable_peripheral_clock`. Returns
+/// `None` for PIDs that aren't present on the selected target, or that
+/// aren't under PMC control.
+fn peripheral_for_pid(pid: u8) -> Option<PeripheralID> {
+    Some(match pid {
+        8 => PeripheralID::UART,
+        #[cfg(feature = "sam3x8h")]
+        10 => PeripheralID::SDRAMC,
+        11 => PeripheralID::PIOA,
+        12 => PeripheralID::PIOB,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        13 => PeripheralID::PIOC,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        14 => PeripheralID::PIOD,
+        #[cfg(feature = "sam3x8h")]
+        15 => PeripheralID::PIOE,
+        #[cfg(feature = "sam3x8h")]
+        16 => PeripheralID::PIOF,
+        17 => PeripheralID::USART0,
+        18 => PeripheralID::USART1,
+        19 => PeripheralID::USART2,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        20 => PeripheralID::USART3,
+        21 => PeripheralID::HSMCI,
+        22 => PeripheralID::TWI0,
+        23 => PeripheralID::TWI1,
+        24 => PeripheralID::SPI0,
+        #[cfg(feature = "sam3x8h")]
+        25 => PeripheralID::SPI1,
+        26 => PeripheralID::SSC,
+        27 => PeripheralID::TC0,
+        28 => PeripheralID::TC1,
+        29 => PeripheralID::TC2,
+        30 => PeripheralID::TC3,
+        31 => PeripheralID::TC4,
+        32 => PeripheralID::TC5,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        33 => PeripheralID::TC6,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        34 => PeripheralID::TC7,
+        #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+        35 => PeripheralID::TC8,
+        36 => PeripheralID::PWM,
+        37 => PeripheralID::ADC,
+        38 => PeripheralID::DACC,
+        39 => PeripheralID::DMAC,
+        40 => PeripheralID::UOTGHS,
+        41 => PeripheralID::TRNG,
+        #[cfg(feature = "sam3x")]
+        42 => PeripheralID::EMAC,
+        43 => PeripheralID::CAN0,
+        44 => PeripheralID::CAN1,
+        _ => return None,
+    })
 }
@@ -15,14 +15,16 @@
 // USART2 | PB21/A | PB20/A | PB24/A | PB22/A | PB23/A |    19     |
 // USART3 | PD05/B | PD04/B | PE16/B | PF05/A | PF04/A |    20     |
 
+mod pins;
+mod rs485;
 mod uart;
-// mod rs485;
 // mod lin;
 // mod spi;
 // mod irda;
 
+pub use self::pins::*;
+pub use self::rs485::*;
 pub use self::uart::*;
-// pub use self::rs485::*;
 // pub use self::lin::*;
 // pub use self::spi::*;
 // pub use self::irda::*;
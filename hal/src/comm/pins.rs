@@ -0,0 +1,72 @@
+//! Type-level mapping from GPIO pins to the `comm` peripheral signals the
+//! PIO multiplexer can actually route them to.
+//!
+//! `into_peripheral_a`/`into_peripheral_b` only flip the `PDR`/`ABSR` bits;
+//! nothing about the pin's type says which peripheral it's wired to, so
+//! nothing stops the result from being handed to any U(S)ART constructor
+//! regardless of whether the datasheet's mux tables wire that pin to that
+//! peripheral at all. The sealed traits here close that gap: they're
+//! implemented only for the `$PinType<PfA>`/`$PinType<PfB>` combinations the
+//! mux table in `comm::uart` lists, so a mis-wired pin is a compile error
+//! instead of a silent no-op.
+
+use crate::gpio::{Pa10, Pa11, Pa12, Pa13, Pa14, Pa8, Pa9, Pb20, Pb21, Pb22, Pb25, PfA};
+use crate::target_device::{UART, USART0, USART1, USART2};
+
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+use crate::gpio::{Pd4, Pd5, PfB};
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+use crate::target_device::USART3;
+#[cfg(feature = "sam3x8h")]
+use crate::gpio::Pf5;
+
+mod private {
+    /// Prevents `RxPin`/`TxPin`/`RtsPin` from being implemented outside this
+    /// module, so their impls stay limited to the mux table below.
+    pub trait Sealed {}
+}
+
+/// A pin wired to `UsartP`'s serial receive (RX) line.
+pub trait RxPin<UsartP>: private::Sealed {}
+
+/// A pin wired to `UsartP`'s serial transmit (TX) line.
+pub trait TxPin<UsartP>: private::Sealed {}
+
+/// A pin wired to `UsartP`'s request-to-send (RTS) line, used by
+/// [`crate::comm::Rs485Builder`] as the transceiver's driver-enable signal.
+pub trait RtsPin<UsartP>: private::Sealed {}
+
+macro_rules! pin_mux {
+    ($Pin:ident, $Mode:ident, $Trait:ident, $UsartP:ident) => {
+        impl private::Sealed for $Pin<$Mode> {}
+        impl $Trait<$UsartP> for $Pin<$Mode> {}
+    };
+}
+
+// UART   | PA08/A | PA09/A |        |        |     8     |
+pin_mux!(Pa8, PfA, RxPin, UART);
+pin_mux!(Pa9, PfA, TxPin, UART);
+
+// USART0 | PA10/A | PA11/A | PB25/A | PB26/A |    17     |
+pin_mux!(Pa10, PfA, RxPin, USART0);
+pin_mux!(Pa11, PfA, TxPin, USART0);
+pin_mux!(Pb25, PfA, RtsPin, USART0);
+
+// USART1 | PA12/A | PA13/A | PA14/A | PA15/A |    18     |
+pin_mux!(Pa12, PfA, RxPin, USART1);
+pin_mux!(Pa13, PfA, TxPin, USART1);
+pin_mux!(Pa14, PfA, RtsPin, USART1);
+
+// USART2 | PB21/A | PB20/A | PB22/A | PB23/A |    19     |
+pin_mux!(Pb21, PfA, RxPin, USART2);
+pin_mux!(Pb20, PfA, TxPin, USART2);
+pin_mux!(Pb22, PfA, RtsPin, USART2);
+
+// USART3 | PD05/B | PD04/B |        |        |    20     | (sam3_e, sam3x8h)
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+pin_mux!(Pd5, PfB, RxPin, USART3);
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+pin_mux!(Pd4, PfB, TxPin, USART3);
+// USART3's RTS is only bonded out on the 217-pin sam3x8h package.
+#[cfg(feature = "sam3x8h")]
+pin_mux!(Pf5, PfA, RtsPin, USART3);
@@ -0,0 +1,180 @@
+//! RS-485 half-duplex mode with automatic driver-enable control.
+//!
+//! Puts a USART into RS-485 mode (`MR.USART_MODE` = `RS485`) and drives its
+//! RTS pin as the transceiver's driver-enable (DE) line. Once configured,
+//! this is entirely handled by the USART hardware: RTS is asserted
+//! automatically before the first byte of a frame and released
+//! `guard_time` bit periods after the shift register empties (`SR.TXEMPTY`),
+//! with no further CPU involvement per byte. This lets the MCU release a
+//! multidrop bus promptly after transmitting instead of holding it forever
+//! or racing the last stop bit off the wire.
+use crate::hal::blocking;
+use crate::hal::serial;
+use crate::target_device::usart0::mr::USART_MODE_A;
+use crate::target_device::usart0::RegisterBlock as UsartRegisterBlock;
+use core::fmt;
+use embedded_hal::prelude::*;
+
+/// Builder for an `Rs485`-mode USART, consuming the RTS pin that the
+/// hardware will drive as driver-enable. Follows the same builder /
+/// terminal-`build` pattern as `crate::watchdog::WdtBuilder`.
+pub struct Rs485Builder<UsartP, RTS> {
+    usart_p: UsartP,
+    _rts: RTS,
+    guard_time: u8,
+}
+
+impl<UsartP, RTS> Rs485Builder<UsartP, RTS>
+where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>,
+    RTS: super::RtsPin<UsartP>,
+{
+    /// Start configuring `usart` for RS-485 half-duplex, with `rts` as the
+    /// transceiver's driver-enable pin. `rts` must be a pin the PIO
+    /// multiplexer actually wires to `usart_p`'s RTS line; any other pin is
+    /// a compile error.
+    pub fn new(usart_p: UsartP, rts: RTS) -> Self {
+        Self {
+            usart_p,
+            _rts: rts,
+            guard_time: 0,
+        }
+    }
+
+    /// Number of bit periods the driver-enable pin is held asserted after
+    /// the shift register empties, giving the last byte's stop bit and any
+    /// transceiver turnaround time a chance to clear the bus before it's
+    /// released. Defaults to 0.
+    pub fn guard_time(mut self, bits: u8) -> Self {
+        self.guard_time = bits;
+        self
+    }
+
+    /// Put the USART into RS-485 mode and program the guard time.
+    pub fn build(self) -> Rs485<UsartP, RTS> {
+        self.usart_p
+            .mr
+            .modify(|_, w| w.usart_mode().variant(USART_MODE_A::RS485));
+        self.usart_p
+            .ttgr
+            .write(|w| unsafe { w.tg().bits(self.guard_time) });
+
+        Rs485 {
+            usart_p: self.usart_p,
+            _rts: self._rts,
+        }
+    }
+}
+
+/// A USART configured for RS-485 half-duplex, produced by
+/// `Rs485Builder::build`. Its RTS pin is driven by the hardware itself as
+/// the bus transceiver's driver-enable line.
+pub struct Rs485<UsartP, RTS> {
+    usart_p: UsartP,
+    _rts: RTS,
+}
+
+impl<UsartP, RTS> Rs485<UsartP, RTS>
+where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>,
+{
+    /// Reprogram the driver-enable guard time (see
+    /// `Rs485Builder::guard_time`).
+    pub fn set_guard_time(&mut self, bits: u8) {
+        self.usart_p.ttgr.write(|w| unsafe { w.tg().bits(bits) });
+    }
+}
+
+impl<UsartP, RTS> serial::Write<u8> for Rs485<UsartP, RTS>
+where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>,
+{
+    type Error = core::convert::Infallible;
+
+    fn try_write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if !self.usart_p.sr.read().txrdy().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.usart_p
+            .thr
+            .write_with_zero(|w| unsafe { w.txchr().bits(word) });
+
+        Ok(())
+    }
+
+    fn try_flush(&mut self) -> nb::Result<(), Self::Error> {
+        if !self.usart_p.sr.read().txempty().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(())
+    }
+}
+
+impl<UsartP, RTS> blocking::serial::write::Default<u8> for Rs485<UsartP, RTS> where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>
+{
+}
+
+impl<UsartP, RTS> serial::Read<u8> for Rs485<UsartP, RTS>
+where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>,
+{
+    type Error = core::convert::Infallible;
+
+    fn try_read(&mut self) -> nb::Result<u8, Self::Error> {
+        if !self.usart_p.sr.read().rxrdy().bit_is_set() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(self.usart_p.rhr.read().rxchr().bits())
+    }
+}
+
+impl<UsartP, RTS> fmt::Write for Rs485<UsartP, RTS>
+where
+    UsartP: core::ops::Deref<Target = UsartRegisterBlock>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+//        |  RTS (DE) | Periph ID |
+// -------+-----------+-----------|
+// USART0 |  PB25/A   |    17     |
+// USART1 |  PA14/A   |    18     |
+// USART2 |  PB22/A   |    19     |
+// USART3 |  PF05/A   |    20     | (sam3x8h only; atsam3_e's USART3 has no RTS/CTS)
+
+use crate::gpio::{Pa14, Pb22, Pb25, PfA};
+use crate::target_device::{USART0, USART1, USART2};
+
+/// RS-485-configured USART0, driving PB25 as hardware DE.
+pub type Rs485Usart0 = Rs485<USART0, Pb25<PfA>>;
+/// Builder for `Rs485Usart0`.
+pub type Rs485Usart0Builder = Rs485Builder<USART0, Pb25<PfA>>;
+
+/// RS-485-configured USART1, driving PA14 as hardware DE.
+pub type Rs485Usart1 = Rs485<USART1, Pa14<PfA>>;
+/// Builder for `Rs485Usart1`.
+pub type Rs485Usart1Builder = Rs485Builder<USART1, Pa14<PfA>>;
+
+/// RS-485-configured USART2, driving PB22 as hardware DE.
+pub type Rs485Usart2 = Rs485<USART2, Pb22<PfA>>;
+/// Builder for `Rs485Usart2`.
+pub type Rs485Usart2Builder = Rs485Builder<USART2, Pb22<PfA>>;
+
+#[cfg(feature = "sam3x8h")]
+use crate::gpio::Pf5;
+#[cfg(feature = "sam3x8h")]
+use crate::target_device::USART3;
+
+/// RS-485-configured USART3, driving PF05 as hardware DE. Only available on
+/// `sam3x8h`; `atsam3_e`'s USART3 has no RTS/CTS pins broken out.
+#[cfg(feature = "sam3x8h")]
+pub type Rs485Usart3 = Rs485<USART3, Pf5<PfA>>;
+/// Builder for `Rs485Usart3`.
+#[cfg(feature = "sam3x8h")]
+pub type Rs485Usart3Builder = Rs485Builder<USART3, Pf5<PfA>>;
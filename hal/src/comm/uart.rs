@@ -1,12 +1,185 @@
 use crate::hal::blocking;
 use crate::hal::serial;
-use crate::target_device::UART;
+use crate::hal::timer::CountDown;
+use crate::target_device::{UART, USART0, USART1, USART2};
 use embedded_hal::prelude::*;
-//use crate::target_device::{UART, USART0, USART1, USART2, USART3};
-use crate::gpio::{Pa8, Pa9, PfA};
+use crate::gpio::{Pa10, Pa11, Pa12, Pa13, Pa8, Pa9, Pb20, Pb21, PfA};
 use crate::target_device::uart::mr::{CHMODE_A, PAR_A};
+use crate::target_device::usart0::mr::{CHRL_A, NBSTOP_A, PAR_A as UsartParity};
 use crate::time::Hertz;
 use core::fmt;
+use starb::{Reader, RingBuffer, Writer};
+
+/// Register-level operations shared by the plain `UART` peripheral and the
+/// `USART0`..`USART3` peripherals, letting `Uart<UsartP, ...>`'s
+/// configuration and transfer methods work against any of them. The common
+/// SAM3X/A U(S)ART register layout uses identical field names and bit
+/// positions for all of this across every instance, so each impl is just a
+/// direct pass-through to the concrete peripheral's own registers.
+pub trait UartInstance {
+    /// Peripheral ID used for enabling this instance's clock and
+    /// configuring its NVIC interrupt line.
+    fn peripheral_id() -> crate::clock::PeripheralID;
+
+    /// Enable the receiver and transmitter and clear any latched status.
+    fn init(&self);
+    /// Set the parity field of `MR` from its raw encoding.
+    fn set_parity_bits(&self, bits: u8);
+    /// Read the parity field of `MR` as its raw encoding.
+    fn parity_bits(&self) -> u8;
+    /// Set the channel mode field of `MR` from its raw encoding.
+    fn set_channel_mode_bits(&self, bits: u8);
+    /// Read the channel mode field of `MR` as its raw encoding.
+    fn channel_mode_bits(&self) -> u8;
+    /// Set the baud rate generator's clock divisor.
+    fn set_baud_divisor(&self, cd: u16);
+    /// Read the baud rate generator's clock divisor.
+    fn baud_divisor(&self) -> u16;
+    /// Whether `THR` is free to accept a new byte.
+    fn txrdy(&self) -> bool;
+    /// Whether `RHR` holds an unread byte.
+    fn rxrdy(&self) -> bool;
+    /// Write a byte to `THR`.
+    fn write_byte(&self, byte: u8);
+    /// Read the byte currently in `RHR`.
+    fn read_byte(&self) -> u8;
+}
+
+macro_rules! impl_uart_instance {
+    ($UsartP:ty, $periph_id:ident) => {
+        impl UartInstance for $UsartP {
+            fn peripheral_id() -> crate::clock::PeripheralID {
+                crate::clock::PeripheralID::$periph_id
+            }
+
+            fn init(&self) {
+                self.cr
+                    .write_with_zero(|w| w.rxen().set_bit().txen().set_bit().rststa().set_bit());
+            }
+
+            fn set_parity_bits(&self, bits: u8) {
+                self.mr.modify(|_, w| unsafe { w.par().bits(bits) });
+            }
+
+            fn parity_bits(&self) -> u8 {
+                self.mr.read().par().bits()
+            }
+
+            fn set_channel_mode_bits(&self, bits: u8) {
+                self.mr.modify(|_, w| unsafe { w.chmode().bits(bits) });
+            }
+
+            fn channel_mode_bits(&self) -> u8 {
+                self.mr.read().chmode().bits()
+            }
+
+            fn set_baud_divisor(&self, cd: u16) {
+                self.brgr.write(|w| unsafe { w.cd().bits(cd) });
+            }
+
+            fn baud_divisor(&self) -> u16 {
+                self.brgr.read().cd().bits()
+            }
+
+            fn txrdy(&self) -> bool {
+                self.sr.read().txrdy().bit_is_set()
+            }
+
+            fn rxrdy(&self) -> bool {
+                self.sr.read().rxrdy().bit_is_set()
+            }
+
+            fn write_byte(&self, byte: u8) {
+                self.thr
+                    .write_with_zero(|w| unsafe { w.txchr().bits(byte) });
+            }
+
+            fn read_byte(&self) -> u8 {
+                self.rhr.read().rxchr().bits()
+            }
+        }
+    };
+}
+
+impl_uart_instance!(UART, UART);
+impl_uart_instance!(USART0, USART0);
+impl_uart_instance!(USART1, USART1);
+impl_uart_instance!(USART2, USART2);
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+impl_uart_instance!(crate::target_device::USART3, USART3);
+
+/// Register-level word-length/stop-bit operations available on the full
+/// `USART0`..`USART3` peripherals but not on the reduced `UART` peripheral,
+/// which is hardwired to 8 data bits and 1 stop bit.
+pub trait UsartInstance: UartInstance {
+    /// Set the character length field of `MR` from its raw encoding.
+    fn set_word_length_bits(&self, bits: u8);
+    /// Read the character length field of `MR` as its raw encoding.
+    fn word_length_bits(&self) -> u8;
+    /// Set the stop bit field of `MR` from its raw encoding.
+    fn set_stop_bits_bits(&self, bits: u8);
+    /// Read the stop bit field of `MR` as its raw encoding.
+    fn stop_bits_bits(&self) -> u8;
+}
+
+macro_rules! impl_usart_instance {
+    ($UsartP:ty) => {
+        impl UsartInstance for $UsartP {
+            fn set_word_length_bits(&self, bits: u8) {
+                self.mr.modify(|_, w| unsafe { w.chrl().bits(bits) });
+            }
+
+            fn word_length_bits(&self) -> u8 {
+                self.mr.read().chrl().bits()
+            }
+
+            fn set_stop_bits_bits(&self, bits: u8) {
+                self.mr.modify(|_, w| unsafe { w.nbstop().bits(bits) });
+            }
+
+            fn stop_bits_bits(&self) -> u8 {
+                self.mr.read().nbstop().bits()
+            }
+        }
+    };
+}
+
+impl_usart_instance!(USART0);
+impl_usart_instance!(USART1);
+impl_usart_instance!(USART2);
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+impl_usart_instance!(crate::target_device::USART3);
+
+/// Ring buffer `Uart0::split`'s RX half is fed from by `handler`, so that
+/// incoming bytes queue up instead of being dropped while the code polling
+/// `Rx0` (or `BufferedUart::read`) is busy elsewhere.
+type RxQueue = RingBuffer<u8>;
+type RxReader = Reader<'static, u8>;
+type RxWriter = Writer<'static, u8>;
+
+static mut RX_QUEUE: RxQueue = RxQueue::new();
+
+/// Interrupt sources controllable via `Uart0::listen`/`Uart0::unlisten`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// A new byte has arrived in `RHR`.
+    RxReady,
+    /// A receive error (see `Error`) was flagged in the status register.
+    RxError(Error),
+    /// `THR` is empty and ready to accept a new byte.
+    TxReady,
+}
+
+/// Receive errors latched in the UART's status register.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// A new character arrived in `RHR` before the previous one was read out.
+    Overrun,
+    /// The expected stop bit wasn't found.
+    Framing,
+    /// The received parity bit didn't match the configured parity scheme.
+    Parity,
+}
 
 /// UART controller configuration
 pub struct Uart<UartP, RX, TX, RTS, CTS> {
@@ -52,23 +225,152 @@ impl<UartP, RX, TX, RTS, CTS> core::ops::DerefMut for Uart<UartP, RX, TX, RTS, C
 /// peripheral A mode.  No hardware flow control.
 pub type Uart0 = Uart<UART, Pa8<PfA>, Pa9<PfA>, (), ()>;
 
-impl Uart0 {
-    /// Instantiate a representation of a UART, providing an interface
-    /// configure, send, and receive on it.
-    pub fn new(uart_p: UART, rx: Pa8<PfA>, tx: Pa9<PfA>) -> Self {
-        let uart0 = Self {
+/// USART0 on its documented default pins (PA10/PA11). No hardware flow
+/// control.
+pub type Usart0 = Uart<USART0, Pa10<PfA>, Pa11<PfA>, (), ()>;
+
+/// USART1 on its documented default pins (PA12/PA13). No hardware flow
+/// control.
+pub type Usart1 = Uart<USART1, Pa12<PfA>, Pa13<PfA>, (), ()>;
+
+/// USART2 on its documented default pins (PB21/PB20). No hardware flow
+/// control.
+pub type Usart2 = Uart<USART2, Pb21<PfA>, Pb20<PfA>, (), ()>;
+
+/// USART3 on its documented default pins (PD05/PD04). No hardware flow
+/// control. Only available on `sam3_e`/`sam3x8h`; the `sam3_c` targets don't
+/// have USART3 at all.
+#[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+pub type Usart3 = Uart<
+    crate::target_device::USART3,
+    crate::gpio::Pd5<crate::gpio::PfB>,
+    crate::gpio::Pd4<crate::gpio::PfB>,
+    (),
+    (),
+>;
+
+impl<UsartP, RX, TX> Uart<UsartP, RX, TX, (), ()>
+where
+    UsartP: UartInstance,
+    RX: super::RxPin<UsartP>,
+    TX: super::TxPin<UsartP>,
+{
+    /// Instantiate a representation of a U(S)ART, providing an interface to
+    /// configure, send, and receive on it. `rx` and `tx` must be pins the
+    /// PIO multiplexer actually wires to `uart_p`'s RX/TX lines (see the mux
+    /// table above); any other pin is a compile error.
+    pub fn new(uart_p: UsartP, rx: RX, tx: TX) -> Self {
+        uart_p.init();
+        Self {
             uart_p,
             _rx: rx,
             _tx: tx,
             _rts: (),
             _cts: (),
-        };
-        uart0
-            .cr
-            .write_with_zero(|w| w.rxen().set_bit().txen().set_bit().rststa().set_bit());
-        uart0
+        }
+    }
+
+    /// Set the serial line parity error correcting strategy from its raw
+    /// `MR.PAR` encoding.
+    pub fn set_parity_bits(&mut self, bits: u8) {
+        self.uart_p.set_parity_bits(bits);
+    }
+
+    /// Get the serial line parity error correcting strategy as its raw
+    /// `MR.PAR` encoding.
+    pub fn parity_bits(&self) -> u8 {
+        self.uart_p.parity_bits()
+    }
+
+    /// Set the serial channel echo/loopback mode from its raw `MR.CHMODE`
+    /// encoding.
+    pub fn set_channel_mode_bits(&mut self, bits: u8) {
+        self.uart_p.set_channel_mode_bits(bits);
+    }
+
+    /// Get the serial channel echo/loopback mode as its raw `MR.CHMODE`
+    /// encoding.
+    pub fn channel_mode_bits(&self) -> u8 {
+        self.uart_p.channel_mode_bits()
+    }
+
+    /// Set the serial line baud rate, which is configured to be a fraction of
+    /// the master clock speed.
+    pub fn set_baudrate<I: Into<Hertz>>(&mut self, baud_rate: I, mck: I) {
+        // cd = mck/(16*baudrate)
+        let cd = mck.into().0 / (baud_rate.into().0 << 4);
+        self.uart_p.set_baud_divisor(cd as u16);
     }
 
+    /// Return the serial line baud rate, calculated to be a fraction of the
+    /// master clock speed.
+    pub fn get_baudrate<I: Into<Hertz>>(&self, mck: I) -> Hertz {
+        // baudrate = mck/(16*cd)
+        Hertz(mck.into().0 / ((self.uart_p.baud_divisor() as u32) << 4))
+    }
+}
+
+/// One-shot framing configuration for a [`Uart`] backed by a full USART
+/// peripheral (`USART0`..`USART3`): baud rate, parity, character length, and
+/// stop bits, applied together by [`Uart::configure`]. Lets e.g. a 7E1 or
+/// 8N2 device be set up without touching each field by hand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    /// Serial line baud rate.
+    pub baud: Hertz,
+    /// Parity error-correcting strategy.
+    pub parity: UsartParity,
+    /// Character length, 5-9 bits.
+    pub word_length: CHRL_A,
+    /// Number of stop bits.
+    pub stop_bits: NBSTOP_A,
+}
+
+impl<UsartP, RX, TX> Uart<UsartP, RX, TX, (), ()>
+where
+    UsartP: UsartInstance,
+{
+    /// Set the character length from its raw `MR.CHRL` encoding (5-9 bits).
+    pub fn set_word_length_bits(&mut self, bits: u8) {
+        self.uart_p.set_word_length_bits(bits);
+    }
+
+    /// Get the character length as its raw `MR.CHRL` encoding.
+    pub fn word_length_bits(&self) -> u8 {
+        self.uart_p.word_length_bits()
+    }
+
+    /// Set the character length.
+    pub fn set_word_length(&mut self, word_length: CHRL_A) {
+        self.uart_p.set_word_length_bits(word_length as u8);
+    }
+
+    /// Set the number of stop bits from its raw `MR.NBSTOP` encoding.
+    pub fn set_stop_bits_bits(&mut self, bits: u8) {
+        self.uart_p.set_stop_bits_bits(bits);
+    }
+
+    /// Get the number of stop bits as its raw `MR.NBSTOP` encoding.
+    pub fn stop_bits_bits(&self) -> u8 {
+        self.uart_p.stop_bits_bits()
+    }
+
+    /// Set the number of stop bits.
+    pub fn set_stop_bits(&mut self, stop_bits: NBSTOP_A) {
+        self.uart_p.set_stop_bits_bits(stop_bits as u8);
+    }
+
+    /// Apply `config`'s baud rate, parity, character length, and stop bits
+    /// in one call, given the current master clock speed `mck`.
+    pub fn configure<I: Into<Hertz>>(&mut self, config: Config, mck: I) {
+        self.set_baudrate(config.baud, mck.into());
+        self.uart_p.set_parity_bits(config.parity as u8);
+        self.set_word_length(config.word_length);
+        self.set_stop_bits(config.stop_bits);
+    }
+}
+
+impl Uart0 {
     /// Set the serial line parity error correcting strategy.
     pub fn set_parity(&mut self, parity: PAR_A) {
         self.mr.write(|w| w.par().variant(parity));
@@ -91,46 +393,227 @@ impl Uart0 {
     pub fn get_channel_mode(&self) -> CHMODE_A {
         self.mr.read().chmode().variant()
     }
+}
 
-    /// Set the serial line baud rate, which is configured to be a fraction of
-    /// the master clock speed.
-    pub fn set_baudrate<I: Into<Hertz>>(&mut self, baud_rate: I, mck: I) {
-        // cd = mck/(16*baudrate)
-        let cd = mck.into().0 / (baud_rate.into().0 << 4);
-        self.brgr.write(|w| unsafe { w.cd().bits(cd as u16) });
+impl Uart0 {
+    /// Enable the interrupt for `event`.
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::RxReady => self.ier.write_with_zero(|w| w.rxrdy().set_bit()),
+            Event::RxError(Error::Overrun) => self.ier.write_with_zero(|w| w.ovre().set_bit()),
+            Event::RxError(Error::Framing) => self.ier.write_with_zero(|w| w.frame().set_bit()),
+            Event::RxError(Error::Parity) => self.ier.write_with_zero(|w| w.pare().set_bit()),
+            Event::TxReady => self.ier.write_with_zero(|w| w.txrdy().set_bit()),
+        }
     }
 
-    /// Return the serial line baud rate, calculated to be a fraction of the
-    /// master clock speed.
-    pub fn get_baudrate<I: Into<Hertz>>(&self, mck: I) -> Hertz {
-        // baudrate = mck/(16*cd)
-        Hertz(mck.into().0 / ((self.brgr.read().cd().bits() as u32) << 4))
+    /// Disable the interrupt for `event`.
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::RxReady => self.idr.write_with_zero(|w| w.rxrdy().set_bit()),
+            Event::RxError(Error::Overrun) => self.idr.write_with_zero(|w| w.ovre().set_bit()),
+            Event::RxError(Error::Framing) => self.idr.write_with_zero(|w| w.frame().set_bit()),
+            Event::RxError(Error::Parity) => self.idr.write_with_zero(|w| w.pare().set_bit()),
+            Event::TxReady => self.idr.write_with_zero(|w| w.txrdy().set_bit()),
+        }
+    }
+
+    /// Return the receive error (if any) currently latched in the status
+    /// register, clearing it via `RSTSTA` so it isn't reported again.
+    pub fn take_error(&mut self) -> Option<Error> {
+        let sr = self.sr.read();
+        let err = if sr.ovre().bit_is_set() {
+            Some(Error::Overrun)
+        } else if sr.frame().bit_is_set() {
+            Some(Error::Framing)
+        } else if sr.pare().bit_is_set() {
+            Some(Error::Parity)
+        } else {
+            None
+        };
+
+        if err.is_some() {
+            self.cr.write_with_zero(|w| w.rststa().set_bit());
+        }
+
+        err
     }
 }
 
-impl From<(UART, Pa8<PfA>, Pa9<PfA>)> for Uart0 {
-    fn from(parts: (UART, Pa8<PfA>, Pa9<PfA>)) -> Self {
+impl<UsartP, RX, TX> From<(UsartP, RX, TX)> for Uart<UsartP, RX, TX, (), ()>
+where
+    UsartP: UartInstance,
+{
+    fn from(parts: (UsartP, RX, TX)) -> Self {
         Self::new(parts.0, parts.1, parts.2)
     }
 }
 
-impl serial::Write<u8> for Uart0 {
+impl<UsartP, RX, TX, RTS, CTS> serial::Write<u8> for Uart<UsartP, RX, TX, RTS, CTS>
+where
+    UsartP: UartInstance,
+{
+    type Error = core::convert::Infallible;
+
+    fn try_write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if !self.uart_p.txrdy() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.uart_p.write_byte(word);
+
+        Ok(())
+    }
+
+    fn try_flush(&mut self) -> nb::Result<(), Self::Error> {
+        if !self.uart_p.txrdy() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(())
+    }
+}
+
+impl<UsartP, RX, TX, RTS, CTS> serial::Read<u8> for Uart<UsartP, RX, TX, RTS, CTS>
+where
+    UsartP: UartInstance,
+{
+    type Error = core::convert::Infallible;
+
+    fn try_read(&mut self) -> nb::Result<u8, Self::Error> {
+        if !self.uart_p.rxrdy() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(self.uart_p.read_byte())
+    }
+}
+
+impl<UsartP, RX, TX, RTS, CTS> blocking::serial::write::Default<u8> for Uart<UsartP, RX, TX, RTS, CTS> where
+    UsartP: UartInstance
+{
+}
+
+impl<UsartP, RX, TX, RTS, CTS> fmt::Write for Uart<UsartP, RX, TX, RTS, CTS>
+where
+    UsartP: UartInstance,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+impl Uart0 {
+    /// Start a PDC-driven background write of `buf`, freeing the CPU to do
+    /// other work while the bytes are clocked out. Poll `pdc_write_complete`
+    /// (or wait for the `ENDTX` interrupt) to find out when `buf` is free
+    /// again.
+    ///
+    /// # Safety
+    ///
+    /// The PDC holds a raw pointer into `buf` for the duration of the
+    /// transfer, so `buf` must remain valid and must not move or be dropped
+    /// until `pdc_write_complete` reports `true`.
+    pub unsafe fn pdc_write(&mut self, buf: &[u8]) {
+        self.tpr.write(|w| w.txptr().bits(buf.as_ptr() as u32));
+        self.tcr.write(|w| w.txctr().bits(buf.len() as u16));
+        self.ptcr.write_with_zero(|w| w.txten().set_bit());
+    }
+
+    /// Whether the most recently started `pdc_write` has finished
+    /// transmitting every byte of its buffer.
+    pub fn pdc_write_complete(&self) -> bool {
+        self.sr.read().endtx().bit_is_set()
+    }
+
+    /// Start a PDC-driven background read into `buf`, freeing the CPU to do
+    /// other work while incoming bytes are clocked in. Poll
+    /// `pdc_read_complete` (or wait for the `ENDRX` interrupt) to find out
+    /// when `buf` has been filled.
+    ///
+    /// # Safety
+    ///
+    /// The PDC holds a raw pointer into `buf` for the duration of the
+    /// transfer, so `buf` must remain valid and must not move or be dropped
+    /// until `pdc_read_complete` reports `true`.
+    pub unsafe fn pdc_read(&mut self, buf: &mut [u8]) {
+        self.rpr.write(|w| w.rxptr().bits(buf.as_mut_ptr() as u32));
+        self.rcr.write(|w| w.rxctr().bits(buf.len() as u16));
+        self.ptcr.write_with_zero(|w| w.rxten().set_bit());
+    }
+
+    /// Whether the most recently started `pdc_read` has filled its buffer.
+    pub fn pdc_read_complete(&self) -> bool {
+        self.sr.read().endrx().bit_is_set()
+    }
+
+    /// Split into independently-ownable transmit and receive halves, so each
+    /// can be moved into a different task or interrupt handler. Enables the
+    /// RXRDY interrupt, so `Rx0` pulls queued bytes out of the RX ring
+    /// buffer fed by `handler`, rather than reading the hardware directly.
+    /// Recombine with `Uart0::join`.
+    pub fn split(mut self) -> (Tx0, Rx0) {
+        self.listen(Event::RxReady);
+        let (rxr, _) = unsafe { RX_QUEUE.split() };
+        (
+            Tx0 {
+                uart_p: self.uart_p,
+                _tx: self._tx,
+            },
+            Rx0 {
+                _rx: self._rx,
+                queue: rxr,
+            },
+        )
+    }
+
+    /// Recombine `tx` and `rx` (produced by `Uart0::split`) back into a
+    /// single `Uart0`, recovering the `UART` peripheral and both pins.
+    /// Disables the RXRDY interrupt `split` enabled.
+    pub fn join(mut tx: Tx0, rx: Rx0) -> Uart0 {
+        tx.uart_p.idr.write_with_zero(|w| w.rxrdy().set_bit());
+        Self {
+            uart_p: tx.uart_p,
+            _rx: rx._rx,
+            _tx: tx._tx,
+            _rts: (),
+            _cts: (),
+        }
+    }
+}
+
+/// Transmit half of a [`Uart0`], produced by [`Uart0::split`].
+pub struct Tx0 {
+    uart_p: UART,
+    _tx: Pa9<PfA>,
+}
+
+/// Receive half of a [`Uart0`], produced by [`Uart0::split`]. Bytes arrive
+/// via the RX ring buffer filled by `handler`, instead of being polled from
+/// the hardware directly, so they aren't lost while `Tx0` or other code is
+/// busy.
+pub struct Rx0 {
+    _rx: Pa8<PfA>,
+    queue: RxReader,
+}
+
+impl serial::Write<u8> for Tx0 {
     type Error = core::convert::Infallible;
 
     fn try_write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
         unsafe {
-            if !self.sr.read().txrdy().bits() {
+            if !self.uart_p.sr.read().txrdy().bits() {
                 return Err(nb::Error::WouldBlock);
             }
 
-            self.thr.write_with_zero(|w| w.txchr().bits(word));
+            self.uart_p.thr.write_with_zero(|w| w.txchr().bits(word));
         }
 
         Ok(())
     }
 
     fn try_flush(&mut self) -> nb::Result<(), Self::Error> {
-        if !self.sr.read().txrdy().bits() {
+        if !self.uart_p.sr.read().txrdy().bits() {
             return Err(nb::Error::WouldBlock);
         }
 
@@ -138,22 +621,124 @@ impl serial::Write<u8> for Uart0 {
     }
 }
 
-impl serial::Read<u8> for Uart0 {
+impl blocking::serial::write::Default<u8> for Tx0 {}
+
+impl fmt::Write for Tx0 {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+impl serial::Read<u8> for Rx0 {
     type Error = core::convert::Infallible;
 
     fn try_read(&mut self) -> nb::Result<u8, Self::Error> {
-        if !self.sr.read().rxrdy().bits() {
-            return Err(nb::Error::WouldBlock);
+        self.queue.shift().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl Rx0 {
+    /// Read a variable-length packet without knowing its length up front:
+    /// fill `buf` from the RX ring buffer until either it's full or the line
+    /// has gone idle for about two character times (20 bit periods) at
+    /// `baud`, whichever comes first. `timer` is restarted after every byte,
+    /// so its expiry with no intervening reset is what signals "idle";
+    /// pass any `CountDown` armed for one-shot use here (e.g.
+    /// `crate::delay::CountDownTimer<SYST>`, or a `TC` channel once one is
+    /// wrapped in the same trait).
+    ///
+    /// This busy-waits for the idle gap (or a full `buf`) rather than
+    /// yielding `WouldBlock`, so it's a plain blocking call, not an `nb`
+    /// one.
+    pub fn read_until_idle<T>(&mut self, buf: &mut [u8], baud: Hertz, timer: &mut T) -> usize
+    where
+        T: CountDown<Time = Hertz>,
+    {
+        let idle_timeout = Hertz((baud.0 / 20).max(1));
+        let mut count = 0;
+
+        let _ = timer.try_start(idle_timeout);
+        while count < buf.len() {
+            match self.queue.shift() {
+                Some(byte) => {
+                    buf[count] = byte;
+                    count += 1;
+                    let _ = timer.try_start(idle_timeout);
+                }
+                None if timer.try_wait().is_ok() => return count,
+                None => {}
+            }
         }
 
-        Ok(self.rhr.read().rxchr().bits())
+        count
     }
 }
 
-impl blocking::serial::write::Default<u8> for Uart0 {}
+/// Drain `UART`'s receive holding register into `events`, clearing any
+/// overrun along the way. Modeled on `crate::uotghs`'s ISR-side `handler`:
+/// called from the UART interrupt vector with the `Writer` half of whichever
+/// ring buffer is feeding the reader(s) in use (`Rx0` or `BufferedUart`).
+fn handler(events: &mut RxWriter) {
+    let uart = unsafe { &*UART::ptr() };
 
-impl fmt::Write for Uart0 {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.try_bwrite_all(s.as_bytes()).map_err(|_| fmt::Error)
+    while uart.sr.read().rxrdy().bit_is_set() {
+        if uart.sr.read().ovre().bit_is_set() {
+            uart.cr.write_with_zero(|w| w.rststa().set_bit());
+        }
+        // Best-effort: if the ring buffer is full, the oldest unread byte
+        // is kept and this one is dropped, rather than blocking the ISR.
+        let _ = events.unshift(uart.rhr.read().rxchr().bits());
     }
 }
+
+/// Drain `UART`'s receive holding register into the RX ring buffer that
+/// feeds `Rx0` and `BufferedUart`. Call this from the UART interrupt vector;
+/// harmless to call even before `Uart0::split` or `BufferedUart::new` has
+/// been used.
+pub fn on_interrupt() {
+    let (_, mut rxw) = unsafe { RX_QUEUE.split() };
+    handler(&mut rxw);
+}
+
+/// A non-blocking, interrupt-fed wrapper around [`Uart0`] that keeps
+/// received bytes in the RX ring buffer instead of requiring every byte to
+/// be polled out of `RHR` before the next one arrives.
+pub struct BufferedUart {
+    uart: Uart0,
+    rx: RxReader,
+}
+
+impl BufferedUart {
+    /// Wrap `uart`, enabling its RXRDY interrupt so incoming bytes are
+    /// queued by `on_interrupt` instead of being dropped between polls.
+    pub fn new(mut uart: Uart0) -> Self {
+        uart.listen(Event::RxReady);
+        let (rx, _) = unsafe { RX_QUEUE.split() };
+        Self { uart, rx }
+    }
+
+    /// Non-blocking read of the next byte out of the RX ring buffer.
+    pub fn read(&mut self) -> nb::Result<u8, core::convert::Infallible> {
+        self.rx.shift().ok_or(nb::Error::WouldBlock)
+    }
+
+    /// Release the underlying `Uart0`, first disabling the RXRDY interrupt.
+    pub fn free(mut self) -> Uart0 {
+        self.uart.unlisten(Event::RxReady);
+        self.uart
+    }
+}
+
+impl serial::Write<u8> for BufferedUart {
+    type Error = core::convert::Infallible;
+
+    fn try_write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.uart.try_write(word)
+    }
+
+    fn try_flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.uart.try_flush()
+    }
+}
+
+impl blocking::serial::write::Default<u8> for BufferedUart {}
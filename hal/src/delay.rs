@@ -1,9 +1,10 @@
 //! Delays
 
 use cortex_m::peripheral::syst::SystClkSource;
-use cortex_m::peripheral::SYST;
+use cortex_m::peripheral::{DWT, SYST};
 
 use crate::hal::blocking::delay::{DelayMs, DelayUs};
+use crate::hal::timer::CountDown;
 use crate::time::Hertz;
 
 /// Timer object for requesting blocking delays, much like sleep().
@@ -94,3 +95,151 @@ impl DelayUs<u8> for Delay<SYST> {
         self.try_delay_us(us as u32)
     }
 }
+
+/// DWT cycle counter as a delay provider.
+///
+/// Unlike `Delay<SYST>`, this doesn't claim the `SYST` peripheral, so
+/// SysTick stays free for an RTOS tick, or for `CountDownTimer<SYST>` below.
+impl Delay<DWT> {
+    /// Configures the DWT cycle counter as a delay provider.
+    ///
+    /// The caller must already have enabled trace (e.g.
+    /// `cortex_m::peripheral::DCB::enable_trace()`), since `DWT` alone has
+    /// no way to turn tracing on.
+    pub fn new(mut dwt: DWT, core_speed: Hertz) -> Self {
+        dwt.enable_cycle_counter();
+
+        Delay {
+            p: dwt,
+            refclock: core_speed,
+        }
+    }
+
+    /// Releases the DWT resource.
+    pub fn free(self) -> DWT {
+        self.p
+    }
+}
+
+impl DelayMs<u32> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_ms(&mut self, ms: u32) -> Result<(), Self::Error> {
+        self.try_delay_us(ms * 1_000)
+    }
+}
+
+impl DelayMs<u16> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_ms(&mut self, ms: u16) -> Result<(), Self::Error> {
+        self.try_delay_ms(ms as u32)
+    }
+}
+
+impl DelayMs<u8> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_ms(&mut self, ms: u8) -> Result<(), Self::Error> {
+        self.try_delay_ms(ms as u32)
+    }
+}
+
+impl DelayUs<u32> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_us(&mut self, us: u32) -> Result<(), Self::Error> {
+        // The cycle counter is a free-running 32-bit counter that (unlike
+        // SysTick's 24-bit reload register) needs no reloading; wrapping
+        // subtraction handles it rolling over mid-wait. One pass can still
+        // only measure less than 2^32 cycles, so delays that don't fit in
+        // half of that are split into several passes.
+        let cycles_per_us = self.refclock.0 / 1_000_000;
+        if cycles_per_us == 0 {
+            return Ok(());
+        }
+        let max_pass_us = (u32::MAX / 2) / cycles_per_us;
+        let mut remaining_us = us;
+        while remaining_us != 0 {
+            let pass_us = remaining_us.min(max_pass_us);
+            let cycles = pass_us * cycles_per_us;
+            let start = DWT::cycle_count();
+            while DWT::cycle_count().wrapping_sub(start) < cycles {}
+            remaining_us -= pass_us;
+        }
+        Ok(())
+    }
+}
+
+impl DelayUs<u16> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_us(&mut self, us: u16) -> Result<(), Self::Error> {
+        self.try_delay_us(us as u32)
+    }
+}
+
+impl DelayUs<u8> for Delay<DWT> {
+    type Error = core::convert::Infallible;
+    fn try_delay_us(&mut self, us: u8) -> Result<(), Self::Error> {
+        self.try_delay_us(us as u32)
+    }
+}
+
+/// A non-blocking, pollable countdown timer.
+pub struct CountDownTimer<PERIPH> {
+    p: PERIPH,
+    refclock: Hertz,
+}
+
+/// System timer (SysTick) as a countdown timer.
+///
+/// Unlike `Delay<SYST>`, which busy-waits for the full period,
+/// `CountDownTimer<SYST>` only arms the counter on `try_start` and reports
+/// whether the period has elapsed on `try_wait`, so callers can poll it
+/// alongside other work instead of blocking.
+impl CountDownTimer<SYST> {
+    /// Configures the system timer (SysTick) as a countdown timer.
+    pub fn new(mut syst: SYST, core_speed: Hertz) -> Self {
+        syst.set_clock_source(SystClkSource::Core);
+        syst.disable_counter();
+
+        CountDownTimer {
+            p: syst,
+            refclock: core_speed,
+        }
+    }
+
+    /// Releases the system timer (SysTick) resource.
+    pub fn free(self) -> SYST {
+        self.p
+    }
+}
+
+impl CountDown for CountDownTimer<SYST> {
+    type Error = core::convert::Infallible;
+    type Time = Hertz;
+
+    /// Start (or restart) a countdown period at `timeout` Hz. If the
+    /// requested period would need more reload ticks than SysTick's 24-bit
+    /// reload register can hold, it's clamped to the longest period a
+    /// single load can count down from.
+    fn try_start<T>(&mut self, timeout: T) -> Result<(), Self::Error>
+    where
+        T: Into<Self::Time>,
+    {
+        const MAX_RVR: u32 = 0x00FF_FFFF;
+        let reload = (self.refclock.0 / timeout.into().0.max(1)).min(MAX_RVR);
+
+        self.p.set_reload(reload);
+        self.p.clear_current();
+        self.p.enable_counter();
+        Ok(())
+    }
+
+    /// Poll whether the current countdown period has elapsed. SysTick
+    /// automatically reloads and keeps counting down on wrap, so once
+    /// started this can be polled in a loop to get a periodic tick.
+    fn try_wait(&mut self) -> nb::Result<(), Self::Error> {
+        if self.p.has_wrapped() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
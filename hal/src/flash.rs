@@ -1,7 +1,152 @@
 //! Configuring the embedded flash controllers.
 use crate::target_device;
+use embedded_storage::nor_flash::{
+    ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+};
 use target_device::{EFC0, EFC1};
 
+/// Size in bytes of one flash page: the unit the latch buffer holds before
+/// an EEFC write command commits it, and so the `NorFlash::WRITE_SIZE`.
+/// The same on every member of the ATSAM3X/A family.
+pub const PAGE_SIZE: usize = 256;
+
+/// Size in bytes of the smallest region `erase_sector`/`NorFlash::erase`
+/// can erase: the smallest granularity the `EPA` command supports, 4 pages.
+pub const SECTOR_SIZE: usize = PAGE_SIZE * 4;
+
+// IFLASH0 is mapped at the same address on every family member; IFLASH1
+// (EFC1's bank, dual-bank parts only) follows immediately after IFLASH0's
+// size, so its base and the total flash size both depend on which part is
+// selected.
+#[cfg(any(feature = "sam3a4c", feature = "sam3x4c", feature = "sam3x4e"))]
+const IFLASH0_SIZE: usize = 256 * 1024;
+#[cfg(any(feature = "sam3a8c", feature = "sam3x8c", feature = "sam3x8e", feature = "sam3x8h"))]
+const IFLASH0_SIZE: usize = 256 * 1024;
+#[cfg(any(feature = "sam3a8c", feature = "sam3x8c", feature = "sam3x8e", feature = "sam3x8h"))]
+const IFLASH1_SIZE: usize = 256 * 1024;
+
+const IFLASH0_ADDR: usize = 0x0008_0000;
+#[cfg(any(feature = "sam3a8c", feature = "sam3x8c", feature = "sam3x8e", feature = "sam3x8h"))]
+const IFLASH1_ADDR: usize = IFLASH0_ADDR + IFLASH0_SIZE;
+
+/// Raw EEFC command codes (the `FCMD` field of `FCR`).
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    /// Write Page.
+    Wp = 0x01,
+    /// Erase Page and Write Page.
+    Ewp = 0x03,
+    /// Erase Pages (4/8/16/32 starting at the given page, per `FARG`).
+    Epa = 0x07,
+    /// Erase All.
+    Ea = 0x05,
+    /// Set Lock Bit.
+    Slb = 0x08,
+    /// Clear Lock Bit.
+    Clb = 0x09,
+    /// Set GPNVM Bit.
+    Sgpb = 0x0B,
+    /// Clear GPNVM Bit.
+    Cgpb = 0x0C,
+    /// Get GPNVM Bits.
+    Ggpb = 0x0D,
+    /// Start Read Unique Identifier.
+    Stui = 0x0E,
+    /// Stop Read Unique Identifier.
+    Spui = 0x0F,
+}
+
+/// Errors from programming, erasing, locking, or reading the embedded
+/// flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashError {
+    /// The targeted region's lock bit prevented the operation.
+    Locked,
+    /// The controller rejected the command or its argument.
+    BadCommand,
+    /// The requested offset/length isn't aligned to `PAGE_SIZE`/`SECTOR_SIZE`
+    /// as the operation requires.
+    NotAligned,
+    /// The requested offset/length falls outside this bank's flash.
+    OutOfBounds,
+}
+
+impl NorFlashError for FlashError {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            FlashError::NotAligned => NorFlashErrorKind::NotAligned,
+            FlashError::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            FlashError::Locked | FlashError::BadCommand => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+/// Register-level EEFC operations shared by `EFC0` and `EFC1`, which have
+/// identical layouts but distinct concrete PAC types.
+pub trait EfcInstance {
+    /// Issue a raw EEFC command with the given page-number/argument field.
+    fn issue_command(&self, command: Command, arg: u16);
+    /// Whether the last command has completed (`FSR.FRDY`).
+    fn ready(&self) -> bool;
+    /// Whether the last command targeted a locked region (`FSR.FLOCKE`).
+    fn lock_error(&self) -> bool;
+    /// Whether the last command or its argument was rejected (`FSR.FCMDE`).
+    fn command_error(&self) -> bool;
+    /// Read back a result word (`FRR`), as produced one-per-read by `GGPB`.
+    fn result(&self) -> u32;
+    /// Base address of this controller's flash bank in the CPU address
+    /// space, where the latch buffer is filled before issuing a write
+    /// command.
+    fn flash_base() -> usize;
+    /// Size in bytes of this controller's flash bank.
+    fn flash_size() -> usize;
+}
+
+macro_rules! impl_efc_instance {
+    ($EfcP:ty, $flash_base:expr, $flash_size:expr) => {
+        impl EfcInstance for $EfcP {
+            fn issue_command(&self, command: Command, arg: u16) {
+                self.fcr.write(|w| unsafe {
+                    w.fkey()
+                        .bits(0x5A)
+                        .farg()
+                        .bits(arg)
+                        .fcmd()
+                        .bits(command as u8)
+                });
+            }
+
+            fn ready(&self) -> bool {
+                self.fsr.read().frdy().bit_is_set()
+            }
+
+            fn lock_error(&self) -> bool {
+                self.fsr.read().flocke().bit_is_set()
+            }
+
+            fn command_error(&self) -> bool {
+                self.fsr.read().fcmde().bit_is_set()
+            }
+
+            fn result(&self) -> u32 {
+                self.frr.read().bits()
+            }
+
+            fn flash_base() -> usize {
+                $flash_base
+            }
+
+            fn flash_size() -> usize {
+                $flash_size
+            }
+        }
+    };
+}
+
+impl_efc_instance!(EFC0, IFLASH0_ADDR, IFLASH0_SIZE);
+#[cfg(any(feature = "sam3a8c", feature = "sam3x8c", feature = "sam3x8e", feature = "sam3x8h"))]
+impl_efc_instance!(EFC1, IFLASH1_ADDR, IFLASH1_SIZE);
+
 /// Flash controller
 pub struct FlashController<EFCn> {
     efc: EFCn,
@@ -21,6 +166,201 @@ impl<E> core::ops::DerefMut for FlashController<E> {
     }
 }
 
+impl<EFCn> FlashController<EFCn>
+where
+    EFCn: EfcInstance,
+{
+    /// Block until the last-issued command completes, then translate any
+    /// latched error flags into a `FlashError`.
+    fn wait_ready(&self) -> Result<(), FlashError> {
+        while !self.efc.ready() {}
+        if self.efc.lock_error() {
+            Err(FlashError::Locked)
+        } else if self.efc.command_error() {
+            Err(FlashError::BadCommand)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fill the flash latch buffer with `data` (up to `PAGE_SIZE` bytes)
+    /// by writing it through the normal flash address space at `page`'s
+    /// address, ahead of issuing a write command.
+    fn fill_latch_buffer(&self, page: u16, data: &[u8]) -> Result<(), FlashError> {
+        if page as usize * PAGE_SIZE + data.len() > EFCn::flash_size() {
+            return Err(FlashError::OutOfBounds);
+        }
+        let page_addr = EFCn::flash_base() + page as usize * PAGE_SIZE;
+        // Safety: `page_addr..page_addr + data.len()` falls within this
+        // controller's flash bank (checked just above) and writes here
+        // only fill the volatile latch buffer; they don't touch flash
+        // cells until a program command is issued below.
+        unsafe {
+            core::ptr::copy_nonoverlapping(data.as_ptr(), page_addr as *mut u8, data.len());
+        }
+        Ok(())
+    }
+
+    /// Program `data` (up to `PAGE_SIZE` bytes) into `page` without erasing
+    /// it first; the page must already be erased (all `0xFF`).
+    pub fn write_page(&mut self, page: u16, data: &[u8]) -> Result<(), FlashError> {
+        self.fill_latch_buffer(page, data)?;
+        self.efc.issue_command(Command::Wp, page);
+        self.wait_ready()
+    }
+
+    /// Erase `page` and program `data` (up to `PAGE_SIZE` bytes) into it in
+    /// one EEFC command.
+    pub fn erase_write_page(&mut self, page: u16, data: &[u8]) -> Result<(), FlashError> {
+        self.fill_latch_buffer(page, data)?;
+        self.efc.issue_command(Command::Ewp, page);
+        self.wait_ready()
+    }
+
+    /// Erase the `SECTOR_SIZE`-aligned 4-page region starting at `page`.
+    pub fn erase_sector(&mut self, page: u16) -> Result<(), FlashError> {
+        if page as usize % (SECTOR_SIZE / PAGE_SIZE) != 0 {
+            return Err(FlashError::NotAligned);
+        }
+        // FARG[1:0] selects the page count for EPA: 0 => 4 pages.
+        self.efc.issue_command(Command::Epa, page);
+        self.wait_ready()
+    }
+
+    /// Erase this controller's entire flash bank.
+    pub fn erase_all(&mut self) -> Result<(), FlashError> {
+        self.efc.issue_command(Command::Ea, 0);
+        self.wait_ready()
+    }
+
+    /// Lock the region containing `page` against programming and erasing.
+    pub fn lock_region(&mut self, page: u16) -> Result<(), FlashError> {
+        self.efc.issue_command(Command::Slb, page);
+        self.wait_ready()
+    }
+
+    /// Unlock the region containing `page`.
+    pub fn unlock_region(&mut self, page: u16) -> Result<(), FlashError> {
+        self.efc.issue_command(Command::Clb, page);
+        self.wait_ready()
+    }
+
+    /// Set GPNVM bit `bit`.
+    pub fn set_gpnvm_bit(&mut self, bit: u8) -> Result<(), FlashError> {
+        self.efc.issue_command(Command::Sgpb, bit as u16);
+        self.wait_ready()
+    }
+
+    /// Clear GPNVM bit `bit`.
+    pub fn clear_gpnvm_bit(&mut self, bit: u8) -> Result<(), FlashError> {
+        self.efc.issue_command(Command::Cgpb, bit as u16);
+        self.wait_ready()
+    }
+
+    /// Read the current value of GPNVM bit `bit`.
+    pub fn gpnvm_bit(&mut self, bit: u8) -> Result<bool, FlashError> {
+        self.efc.issue_command(Command::Ggpb, 0);
+        self.wait_ready()?;
+        Ok(self.efc.result() & (1 << bit) != 0)
+    }
+
+    /// Read the 128-bit unique device identifier. While the read is in
+    /// progress, this bank's flash reads return the identifier's 4 words
+    /// instead of flash contents, which is why this needs exclusive access
+    /// to `self` for the duration rather than exposing `STUI`/`SPUI`
+    /// separately.
+    pub fn unique_id(&mut self) -> Result<[u32; 4], FlashError> {
+        self.efc.issue_command(Command::Stui, 0);
+        self.wait_ready()?;
+
+        let mut id = [0u32; 4];
+        let base = EFCn::flash_base() as *const u32;
+        for (i, word) in id.iter_mut().enumerate() {
+            // Safety: while STUI is active, reads from the start of this
+            // bank's flash address space return the identifier words
+            // rather than flash contents.
+            *word = unsafe { core::ptr::read_volatile(base.add(i)) };
+        }
+
+        self.efc.issue_command(Command::Spui, 0);
+        self.wait_ready()?;
+        Ok(id)
+    }
+}
+
+impl<EFCn> ErrorType for FlashController<EFCn>
+where
+    EFCn: EfcInstance,
+{
+    type Error = FlashError;
+}
+
+impl<EFCn> ReadNorFlash for FlashController<EFCn>
+where
+    EFCn: EfcInstance,
+{
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        if offset + bytes.len() > EFCn::flash_size() {
+            return Err(FlashError::OutOfBounds);
+        }
+        let src = (EFCn::flash_base() + offset) as *const u8;
+        // Safety: bounds were just checked against this bank's flash size.
+        unsafe {
+            core::ptr::copy_nonoverlapping(src, bytes.as_mut_ptr(), bytes.len());
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        EFCn::flash_size()
+    }
+}
+
+impl<EFCn> NorFlash for FlashController<EFCn>
+where
+    EFCn: EfcInstance,
+{
+    const WRITE_SIZE: usize = PAGE_SIZE;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if from as usize % SECTOR_SIZE != 0 || to as usize % SECTOR_SIZE != 0 {
+            return Err(FlashError::NotAligned);
+        }
+        if to as usize > EFCn::flash_size() {
+            return Err(FlashError::OutOfBounds);
+        }
+
+        let pages_per_sector = (SECTOR_SIZE / PAGE_SIZE) as u16;
+        let mut page = (from as usize / PAGE_SIZE) as u16;
+        let end_page = (to as usize / PAGE_SIZE) as u16;
+        while page < end_page {
+            self.erase_sector(page)?;
+            page += pages_per_sector;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        if offset as usize % PAGE_SIZE != 0 || bytes.len() % PAGE_SIZE != 0 {
+            return Err(FlashError::NotAligned);
+        }
+        if offset as usize + bytes.len() > EFCn::flash_size() {
+            return Err(FlashError::OutOfBounds);
+        }
+
+        let mut page = (offset as usize / PAGE_SIZE) as u16;
+        for chunk in bytes.chunks(PAGE_SIZE) {
+            self.write_page(page, chunk)?;
+            page += 1;
+        }
+        Ok(())
+    }
+}
+
 /// Embedded flash controller 0 (EFC0)
 pub type FlashController0 = FlashController<EFC0>;
 impl FlashController<EFC0> {
@@ -0,0 +1,171 @@
+//! Signed firmware update receiver.
+//!
+//! Lets a deployed board receive a new application image over
+//! [`crate::comm::Uart0`] and verify it before handing it off to be written
+//! to flash. The image is transferred with a small XMODEM-style framed
+//! protocol (128-byte blocks, each prefixed with `SOH`/block#/~block# and
+//! trailed with a CRC-16, ACKed or NAKed one block at a time), then a
+//! detached Ed25519 signature over the whole image is checked against a
+//! compile-time-embedded public key via the `salty` no-std crate before
+//! [`receive_and_apply`] calls the caller's flash-write callback.
+//!
+//! This is opt-in (feature `fwupdate`) since it pulls in `salty` and isn't
+//! needed by boards that update some other way (e.g. over USB, or not at
+//! all).
+//!
+//! The staging buffer the image is received into (typically backed by
+//! `crate::sdramc`, since a full application image rarely fits in the 96KB
+//! of on-chip SRAM alongside the running program) must be a region distinct
+//! from the code currently executing - overwriting the running image while
+//! it's still being fetched from flash would corrupt the update in
+//! progress.
+use crate::comm::Uart0;
+use embedded_hal::prelude::*;
+
+const SOH: u8 = 0x01;
+const EOT: u8 = 0x04;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18;
+const BLOCK_SIZE: usize = 128;
+
+/// Errors that can occur receiving or applying a firmware update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FwUpdateError {
+    /// The staging buffer filled up before an `EOT` was received.
+    BufferFull,
+    /// The received image's Ed25519 signature did not verify against the
+    /// provided public key.
+    SignatureInvalid,
+    /// The caller-provided flash-write callback failed.
+    FlashWriteFailed,
+}
+
+/// Compute the CRC-16/XMODEM checksum (poly `0x1021`, init `0x0000`, no
+/// reflection) XMODEM's 128-byte block protocol uses to validate each block.
+fn crc16_xmodem(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Verify a detached Ed25519 signature over `image`, using `pubkey` as the
+/// trusted signer. Exposed standalone so it can be reused outside of the
+/// UART receiver, e.g. to re-check an image already staged by some other
+/// path.
+pub fn verify(image: &[u8], sig: &[u8; 64], pubkey: &[u8; 32]) -> bool {
+    let pubkey = match salty::PublicKey::try_from(pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return false,
+    };
+    let signature = match salty::Signature::try_from(sig.as_slice()) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    pubkey.verify(image, &signature).is_ok()
+}
+
+/// Receives an XMODEM-framed image into a caller-provided staging buffer.
+pub struct FwUpdateReceiver<'a> {
+    uart: Uart0,
+    buffer: &'a mut [u8],
+}
+
+impl<'a> FwUpdateReceiver<'a> {
+    /// Start a receiver that reads blocks from `uart` into `buffer`.
+    pub fn new(uart: Uart0, buffer: &'a mut [u8]) -> Self {
+        Self { uart, buffer }
+    }
+
+    /// Run the XMODEM receive state machine to completion, blocking until
+    /// the sender signals `EOT` (success) or the staging buffer fills up
+    /// (`FwUpdateError::BufferFull`). Returns the number of bytes received.
+    pub fn receive(&mut self) -> Result<usize, FwUpdateError> {
+        let mut expected_block: u8 = 1;
+        let mut offset = 0usize;
+
+        loop {
+            match nb::block!(self.uart.try_read()).unwrap_or(CAN) {
+                EOT => {
+                    let _ = nb::block!(self.uart.try_write(ACK));
+                    return Ok(offset);
+                }
+                SOH => {
+                    let block_num = nb::block!(self.uart.try_read()).unwrap_or(0);
+                    let block_num_inv = nb::block!(self.uart.try_read()).unwrap_or(0);
+
+                    let mut payload = [0u8; BLOCK_SIZE];
+                    for byte in payload.iter_mut() {
+                        *byte = nb::block!(self.uart.try_read()).unwrap_or(0);
+                    }
+                    let crc_hi = nb::block!(self.uart.try_read()).unwrap_or(0);
+                    let crc_lo = nb::block!(self.uart.try_read()).unwrap_or(0);
+                    let crc_received = u16::from_be_bytes([crc_hi, crc_lo]);
+
+                    let block_ok = block_num == !block_num_inv;
+                    let crc_ok = crc16_xmodem(&payload) == crc_received;
+
+                    if !block_ok || !crc_ok {
+                        let _ = nb::block!(self.uart.try_write(NAK));
+                        continue;
+                    }
+
+                    // A retransmit of the block we already stored is simply
+                    // re-ACKed without copying it again.
+                    if block_num == expected_block {
+                        if offset + BLOCK_SIZE > self.buffer.len() {
+                            let _ = nb::block!(self.uart.try_write(CAN));
+                            return Err(FwUpdateError::BufferFull);
+                        }
+                        self.buffer[offset..offset + BLOCK_SIZE].copy_from_slice(&payload);
+                        offset += BLOCK_SIZE;
+                        expected_block = expected_block.wrapping_add(1);
+                    }
+
+                    let _ = nb::block!(self.uart.try_write(ACK));
+                }
+                _ => {
+                    let _ = nb::block!(self.uart.try_write(NAK));
+                }
+            }
+        }
+    }
+}
+
+/// Receive an image over `uart` into `staging`, verify its detached Ed25519
+/// `sig` against `pubkey`, and hand the validated image to `flash_write`.
+///
+/// `staging` must be a memory region distinct from the code currently
+/// running (see the module-level docs); this function has no way to verify
+/// that on its own.
+pub fn receive_and_apply<F>(
+    uart: Uart0,
+    staging: &mut [u8],
+    sig: &[u8; 64],
+    pubkey: &[u8; 32],
+    flash_write: F,
+) -> Result<(), FwUpdateError>
+where
+    F: FnOnce(&[u8]) -> Result<(), ()>,
+{
+    let len = {
+        let mut receiver = FwUpdateReceiver::new(uart, staging);
+        receiver.receive()?
+    };
+    let image = &staging[..len];
+
+    if !verify(image, sig, pubkey) {
+        return Err(FwUpdateError::SignatureInvalid);
+    }
+
+    flash_write(image).map_err(|_| FwUpdateError::FlashWriteFailed)
+}
@@ -1,5 +1,6 @@
 //! Configuring the GPIO pins
 use crate::hal::digital::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+use crate::time::Hertz;
 
 // SAM3A4C, SAM3A8C, SAM3X4C, and SAM3X8C (100-pin) only have PIOA-PIOB
 #[cfg(feature = "sam3_c")]
@@ -68,12 +69,217 @@ pub struct PfA;
 /// Peripheral Function B
 pub struct PfB;
 
+/// Selects which input transition(s) an `Input` pin's interrupt fires on.
+///
+/// `AnyEdge` uses the PIO controller's basic input-change detection; the
+/// rest switch the pin into "additional interrupt mode" (`AIMER`) to
+/// narrow detection to a single edge or level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptTrigger {
+    /// Fire on either a rising or a falling edge.
+    AnyEdge,
+    /// Fire only on a low-to-high transition.
+    RisingEdge,
+    /// Fire only on a high-to-low transition.
+    FallingEdge,
+    /// Fire continuously while the pin reads high.
+    HighLevel,
+    /// Fire continuously while the pin reads low.
+    LowLevel,
+}
+
+/// Marker for a pin whose mode is tracked at runtime (`DynamicPin`)
+/// instead of encoded in the type.
+pub struct Dynamic;
+
+/// A `DynamicPin`'s current runtime-tracked configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinMode {
+    /// Floating input.
+    FloatingInput,
+    /// Pulled-up input.
+    PullUpInput,
+    /// Push-pull output.
+    PushPullOutput,
+    /// Open-drain output.
+    OpenDrainOutput,
+}
+
+/// Returned by a `DynamicPin` accessor called while the pin is in a mode
+/// that doesn't support it (e.g. `set_high` while configured as an input).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPinMode;
+
+/// Identifies which PIO controller an `ErasedPin` belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Port {
+    /// PIOA
+    A,
+    /// PIOB
+    B,
+    /// PIOC
+    #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+    C,
+    /// PIOD
+    #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+    D,
+    /// PIOE
+    #[cfg(feature = "sam3x8h")]
+    E,
+    /// PIOF
+    #[cfg(feature = "sam3x8h")]
+    F,
+}
+
+/// A pin whose concrete group and pin number have been erased to runtime
+/// state, so pins from different PIO groups can be stored together in an
+/// array or struct (e.g. `[ErasedPin<Output<PushPull>>; 4]`). Obtained via
+/// `into_erased()` on any configured pin.
+pub struct ErasedPin<MODE> {
+    port: Port,
+    pin_no: u8,
+    _mode: PhantomData<MODE>,
+}
+
+impl<MODE> ErasedPin<MODE> {
+    /// Test the logic level of the pin; if it is currently high, return true.
+    /// If the pin's group is not currently clocked, this will return the
+    /// value from when it was last clocked.
+    pub fn is_high(&self) -> bool {
+        let mask = 1u32 << self.pin_no;
+        let bits = match self.port {
+            Port::A => unsafe { (*PIOA::ptr()).pdsr.read().bits() },
+            Port::B => unsafe { (*PIOB::ptr()).pdsr.read().bits() },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::C => unsafe { (*PIOC::ptr()).pdsr.read().bits() },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::D => unsafe { (*PIOD::ptr()).pdsr.read().bits() },
+            #[cfg(feature = "sam3x8h")]
+            Port::E => unsafe { (*PIOE::ptr()).pdsr.read().bits() },
+            #[cfg(feature = "sam3x8h")]
+            Port::F => unsafe { (*PIOF::ptr()).pdsr.read().bits() },
+        };
+        bits & mask != 0
+    }
+
+    /// Test the logic level of the pin; if it is currently low, return true.
+    /// If the pin's group is not currently clocked, this will return the
+    /// value from when it was last clocked.
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+impl<MODE> InputPin for ErasedPin<MODE> {
+    type Error = core::convert::Infallible;
+
+    fn try_is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_high())
+    }
+
+    fn try_is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_low())
+    }
+}
+
+impl<MODE> ErasedPin<Output<MODE>> {
+    fn set_high_impl(&mut self) {
+        let mask = 1u32 << self.pin_no;
+        match self.port {
+            Port::A => unsafe { (*PIOA::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+            Port::B => unsafe { (*PIOB::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::C => unsafe { (*PIOC::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::D => unsafe { (*PIOD::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(feature = "sam3x8h")]
+            Port::E => unsafe { (*PIOE::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(feature = "sam3x8h")]
+            Port::F => unsafe { (*PIOF::ptr()).sodr.write_with_zero(|w| w.bits(mask)) },
+        }
+    }
+
+    fn set_low_impl(&mut self) {
+        let mask = 1u32 << self.pin_no;
+        match self.port {
+            Port::A => unsafe { (*PIOA::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+            Port::B => unsafe { (*PIOB::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::C => unsafe { (*PIOC::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(any(feature = "sam3_e", feature = "sam3x8h"))]
+            Port::D => unsafe { (*PIOD::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(feature = "sam3x8h")]
+            Port::E => unsafe { (*PIOE::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+            #[cfg(feature = "sam3x8h")]
+            Port::F => unsafe { (*PIOF::ptr()).codr.write_with_zero(|w| w.bits(mask)) },
+        }
+    }
+
+    /// Toggle the logic level of the pin; if it is currently high, set it low
+    /// and vice-versa.
+    pub fn toggle(&mut self) {
+        if self.is_high() {
+            self.set_low_impl();
+        } else {
+            self.set_high_impl();
+        }
+    }
+
+    /// Set the logic level of the pin high.
+    pub fn set_high(&mut self) {
+        self.set_high_impl();
+    }
+
+    /// Set the logic level of the pin low.
+    pub fn set_low(&mut self) {
+        self.set_low_impl();
+    }
+}
+
+impl<MODE> ToggleableOutputPin for ErasedPin<Output<MODE>> {
+    type Error = core::convert::Infallible;
+
+    fn try_toggle(&mut self) -> Result<(), Self::Error> {
+        if self.is_high() {
+            self.set_low_impl();
+        } else {
+            self.set_high_impl();
+        }
+        Ok(())
+    }
+}
+
+impl<MODE> OutputPin for ErasedPin<Output<MODE>> {
+    type Error = core::convert::Infallible;
+
+    fn try_set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_high_impl();
+        Ok(())
+    }
+
+    fn try_set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_low_impl();
+        Ok(())
+    }
+}
+
+impl<MODE> StatefulOutputPin for ErasedPin<Output<MODE>> {
+    fn try_is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_high())
+    }
+
+    fn try_is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_low())
+    }
+}
+
 macro_rules! pin {
     (
         $group:ident,
         $PinType:ident,
         $pin_ident:ident,
-        $pin_no:expr
+        $pin_no:expr,
+        $group_id:ident
     ) => {
         crate::paste! {
         /// Represents the IO pin with the matching name.
@@ -177,6 +383,20 @@ macro_rules! pin {
                 } // end paste
                 $PinType { _mode: PhantomData }
             }
+
+            /// Erase this pin's concrete type, so it can be stored alongside
+            /// pins from other PIO groups in an array or struct. The pin's
+            /// current configuration is untouched; only how it's addressed
+            /// changes.
+            pub fn into_erased(self) -> ErasedPin<MODE> {
+                crate::paste! {
+                ErasedPin {
+                    port: Port::[<$group_id:upper>],
+                    pin_no: $pin_no,
+                    _mode: PhantomData,
+                }
+                } // end paste
+            }
         }
 
         impl<MODE> $PinType<Output<MODE>> {
@@ -279,6 +499,219 @@ macro_rules! pin {
                 Ok(self.is_low())
             }
         }
+
+        crate::paste! {
+        /// This pin, but with its mode tracked at runtime instead of in
+        /// the type; obtained via `into_dynamic()`.
+        pub struct [<Dynamic $PinType>] {
+            mode: PinMode,
+        }
+        } // end paste
+
+        crate::paste! {
+        impl $PinType<Input<Floating>> {
+            /// Convert to a `DynamicPin`, remembering it's a floating input.
+            pub fn into_dynamic(self) -> [<Dynamic $PinType>] {
+                [<Dynamic $PinType>] { mode: PinMode::FloatingInput }
+            }
+        }
+
+        impl $PinType<Input<PullUp>> {
+            /// Convert to a `DynamicPin`, remembering it's a pulled-up input.
+            pub fn into_dynamic(self) -> [<Dynamic $PinType>] {
+                [<Dynamic $PinType>] { mode: PinMode::PullUpInput }
+            }
+        }
+
+        impl $PinType<Output<PushPull>> {
+            /// Convert to a `DynamicPin`, remembering it's a push-pull output.
+            pub fn into_dynamic(self) -> [<Dynamic $PinType>] {
+                [<Dynamic $PinType>] { mode: PinMode::PushPullOutput }
+            }
+        }
+
+        impl $PinType<Output<OpenDrain>> {
+            /// Convert to a `DynamicPin`, remembering it's an open-drain output.
+            pub fn into_dynamic(self) -> [<Dynamic $PinType>] {
+                [<Dynamic $PinType>] { mode: PinMode::OpenDrainOutput }
+            }
+        }
+        } // end paste
+
+        crate::paste! {
+        impl [<Dynamic $PinType>] {
+            /// This pin's current runtime-tracked mode.
+            pub fn mode(&self) -> PinMode {
+                self.mode
+            }
+
+            /// Reconfigure as a floating input.
+            pub fn make_floating_input(&mut self) {
+                unsafe {(*$group::ptr()).per.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).odr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).pudr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                self.mode = PinMode::FloatingInput;
+            }
+
+            /// Reconfigure as a pulled-up input.
+            pub fn make_pull_up_input(&mut self) {
+                unsafe {(*$group::ptr()).per.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).odr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).puer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                self.mode = PinMode::PullUpInput;
+            }
+
+            /// Reconfigure as a push-pull output.
+            pub fn make_push_pull_output(&mut self) {
+                unsafe {(*$group::ptr()).per.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).oer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).mddr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).pudr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                self.mode = PinMode::PushPullOutput;
+            }
+
+            /// Reconfigure as an open-drain output.
+            pub fn make_open_drain_output(&mut self) {
+                unsafe {(*$group::ptr()).per.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).oer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).mder.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                self.mode = PinMode::OpenDrainOutput;
+            }
+
+            /// Set the pin's logic level high. Fails if the pin isn't
+            /// currently configured as an output.
+            pub fn set_high(&mut self) -> Result<(), InvalidPinMode> {
+                match self.mode {
+                    PinMode::PushPullOutput | PinMode::OpenDrainOutput => {
+                        unsafe {(*$group::ptr()).sodr.write_with_zero(|w| w.[<p $pin_no>]().bit(true));}
+                        Ok(())
+                    }
+                    _ => Err(InvalidPinMode),
+                }
+            }
+
+            /// Set the pin's logic level low. Fails if the pin isn't
+            /// currently configured as an output.
+            pub fn set_low(&mut self) -> Result<(), InvalidPinMode> {
+                match self.mode {
+                    PinMode::PushPullOutput | PinMode::OpenDrainOutput => {
+                        unsafe {(*$group::ptr()).codr.write_with_zero(|w| w.[<p $pin_no>]().bit(true));}
+                        Ok(())
+                    }
+                    _ => Err(InvalidPinMode),
+                }
+            }
+
+            /// Test whether the pin's logic level is high. Fails if the pin
+            /// isn't currently configured as an input.
+            pub fn is_high(&self) -> Result<bool, InvalidPinMode> {
+                match self.mode {
+                    PinMode::FloatingInput | PinMode::PullUpInput => {
+                        Ok(unsafe {(*$group::ptr()).pdsr.read().[<p $pin_no>]().bits()})
+                    }
+                    _ => Err(InvalidPinMode),
+                }
+            }
+
+            /// Test whether the pin's logic level is low. Fails if the pin
+            /// isn't currently configured as an input.
+            pub fn is_low(&self) -> Result<bool, InvalidPinMode> {
+                self.is_high().map(|high| !high)
+            }
+        }
+        } // end paste
+
+        impl<MODE> $PinType<Input<MODE>> {
+            /// Enable this pin's PIO input-change interrupt, configured to
+            /// fire on `trigger`. Use `PioGroup::take_pending` on this
+            /// pin's group to read (and clear) which pins fired.
+            pub fn enable_interrupt(&mut self, trigger: InterruptTrigger) {
+                crate::paste! {
+                match trigger {
+                    InterruptTrigger::AnyEdge => {
+                        // Plain input-change detection: no additional mode.
+                        unsafe {(*$group::ptr()).aimdr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                    }
+                    InterruptTrigger::RisingEdge => {
+                        unsafe {(*$group::ptr()).aimer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).esr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).rehlsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                    }
+                    InterruptTrigger::FallingEdge => {
+                        unsafe {(*$group::ptr()).aimer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).esr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).fellsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                    }
+                    InterruptTrigger::HighLevel => {
+                        unsafe {(*$group::ptr()).aimer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).lsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).rehlsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                    }
+                    InterruptTrigger::LowLevel => {
+                        unsafe {(*$group::ptr()).aimer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).lsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                        unsafe {(*$group::ptr()).fellsr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                    }
+                }
+                unsafe {(*$group::ptr()).ier.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                } // end paste
+            }
+
+            /// Disable this pin's PIO input-change interrupt and revert it
+            /// to plain any-edge detection, so the next `enable_interrupt`
+            /// call starts from a known state regardless of `trigger`.
+            pub fn disable_interrupt(&mut self) {
+                crate::paste! {
+                unsafe {(*$group::ptr()).idr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).aimdr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                } // end paste
+            }
+
+            /// Enable this pin's input filter in glitch-filter mode, which
+            /// rejects any pulse shorter than one master-clock period.
+            pub fn glitch_filter(&mut self) {
+                crate::paste! {
+                unsafe {(*$group::ptr()).ifscdr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).ifer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                } // end paste
+            }
+
+            /// Enable this pin's input filter in debounce mode, sourced from
+            /// the slow clock, and program the cutoff frequency of the whole
+            /// PIO group's debounce divider (`SCDR`) so that pulses shorter
+            /// than `1 / (2 * cutoff_hz)` are rejected.
+            ///
+            /// `SCDR` is shared across every pin in this group: a later call
+            /// to `debounce_filter` on any pin in the same group overrides
+            /// the divider for all of them.
+            pub fn debounce_filter<I: Into<Hertz>>(&mut self, slow_clock: I, cutoff_hz: I) {
+                // f = slck / (2*(div+1))  =>  div = slck/(2*f) - 1
+                let slck = slow_clock.into().0;
+                let cutoff_hz = cutoff_hz.into().0.max(1);
+                let div = (slck / (2 * cutoff_hz)).saturating_sub(1);
+                crate::paste! {
+                unsafe {(*$group::ptr()).ifscer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                unsafe {(*$group::ptr()).ifer.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                } // end paste
+                unsafe {(*$group::ptr()).scdr.write(|w| w.div().bits(div as u16));}
+            }
+
+            /// Disable this pin's input filter entirely, whichever mode it
+            /// was configured in.
+            pub fn disable_filter(&mut self) {
+                crate::paste! {
+                unsafe {(*$group::ptr()).ifdr.write_with_zero(|w| w.[<p $pin_no>]().set_bit());}
+                } // end paste
+            }
+
+            /// Report whether this pin's input filter is currently enabled
+            /// (`IFSR`).
+            pub fn filter_enabled(&self) -> bool {
+                crate::paste! {
+                unsafe {(*$group::ptr()).ifsr.read().[<p $pin_no>]().bit_is_set()}
+                } // end paste
+            }
+        }
     };
 } // End `pin` macro definition
 
@@ -315,6 +748,15 @@ impl PioGroup<[<PIO $group_id:upper>]> {
     }
     )+
 
+    /// Read and clear this group's pending input-change interrupts,
+    /// returning a bitmask with one bit set per pin number that fired.
+    /// `ISR` is clear-on-read, so this must be called exactly once per
+    /// interrupt and the returned mask consulted for every pin, or events
+    /// on pins other than the first one checked will be lost.
+    pub fn take_pending(&mut self) -> u32 {
+        self.group.isr.read().bits()
+    }
+
 }
 
 impl From<[<PIO $group_id:upper>]> for PioGroup<[<PIO $group_id:upper>]> {
@@ -324,7 +766,7 @@ impl From<[<PIO $group_id:upper>]> for PioGroup<[<PIO $group_id:upper>]> {
 }
 
 $(
-    pin!([<PIO $group_id:upper>], [<P $group_id:lower $pin_no>], [<p $group_id:lower $pin_no>], $pin_no);
+    pin!([<PIO $group_id:upper>], [<P $group_id:lower $pin_no>], [<p $group_id:lower $pin_no>], $pin_no, $group_id);
 )+
 } // end paste
     };
@@ -51,8 +51,12 @@ pub mod clock;
 pub mod comm;
 pub mod delay;
 pub mod flash;
+#[cfg(feature = "fwupdate")]
+pub mod fwupdate;
 pub mod gpio;
 pub mod prelude;
+#[cfg(feature = "sam3x8h")]
+pub mod sdramc;
 pub mod time;
 pub mod watchdog;
 
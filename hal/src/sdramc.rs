@@ -0,0 +1,367 @@
+//! Driver for the External Bus Interface's SDRAM controller (SDRAMC), which
+//! maps an external SDRAM chip into the CPU's address space.
+//!
+//! Unlike the on-chip 96KB SRAM, an external SDRAM chip is large enough to
+//! back a heap or a framebuffer, but two things have to happen before this
+//! driver can bring it up:
+//! - the EBI pins (data/address/control) must be muxed to their SDRAM
+//!   peripheral function, via [`crate::gpio`]
+//! - the SDRAM chip select must be enabled on the bus matrix, via
+//!   [`crate::bus::BusInterconnect`]
+//!
+//! Both of those are board-specific and are the caller's responsibility;
+//! this driver only runs the SDRAMC power-up sequence mandated by the
+//! datasheet once the chip is electrically reachable.
+use crate::target_device;
+use crate::time::Hertz;
+use target_device::SDRAMC;
+
+/// Base address the SDRAM chip select (`NCS1`) is mapped to on the EBI.
+pub const SDRAM_BASE_ADDRESS: usize = 0x7000_0000;
+
+/// Number of column address bits on the external SDRAM device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnBits {
+    /// 8 column address bits (256 columns)
+    Eight,
+    /// 9 column address bits (512 columns)
+    Nine,
+    /// 10 column address bits (1024 columns)
+    Ten,
+    /// 11 column address bits (2048 columns)
+    Eleven,
+}
+
+impl ColumnBits {
+    fn bits(self) -> u8 {
+        match self {
+            ColumnBits::Eight => 0,
+            ColumnBits::Nine => 1,
+            ColumnBits::Ten => 2,
+            ColumnBits::Eleven => 3,
+        }
+    }
+
+    fn count(self) -> u32 {
+        8 + self.bits() as u32
+    }
+}
+
+/// Number of row address bits on the external SDRAM device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowBits {
+    /// 11 row address bits (2048 rows)
+    Eleven,
+    /// 12 row address bits (4096 rows)
+    Twelve,
+    /// 13 row address bits (8192 rows)
+    Thirteen,
+}
+
+impl RowBits {
+    fn bits(self) -> u8 {
+        match self {
+            RowBits::Eleven => 0,
+            RowBits::Twelve => 1,
+            RowBits::Thirteen => 2,
+        }
+    }
+
+    fn count(self) -> u32 {
+        11 + self.bits() as u32
+    }
+}
+
+/// Number of internal banks on the external SDRAM device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BankCount {
+    /// 2 internal banks
+    Two,
+    /// 4 internal banks
+    Four,
+}
+
+impl BankCount {
+    fn bits(self) -> u8 {
+        match self {
+            BankCount::Two => 0,
+            BankCount::Four => 1,
+        }
+    }
+
+    fn count(self) -> u32 {
+        match self {
+            BankCount::Two => 2,
+            BankCount::Four => 4,
+        }
+    }
+}
+
+/// CAS latency, in master clock cycles, of the external SDRAM device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CasLatency {
+    /// 1 cycle CAS latency
+    One,
+    /// 2 cycle CAS latency
+    Two,
+    /// 3 cycle CAS latency
+    Three,
+}
+
+impl CasLatency {
+    fn bits(self) -> u8 {
+        match self {
+            CasLatency::One => 1,
+            CasLatency::Two => 2,
+            CasLatency::Three => 3,
+        }
+    }
+}
+
+/// Configuration for bringing up an external SDRAM chip, following the
+/// `Builder`/terminal-method pattern used by [`crate::watchdog::WdtBuilder`].
+///
+/// Defaults match a common 512Mbit (32M x16) SDRAM: 8K rows, 1K columns,
+/// 4 banks, CAS latency 2, and the JEDEC-standard timings for a -7 speed
+/// grade part.
+pub struct SdramcBuilder {
+    sdramc: SDRAMC,
+    mck: Hertz,
+    columns: ColumnBits,
+    rows: RowBits,
+    banks: BankCount,
+    cas_latency: CasLatency,
+    /// Write recovery delay, in master clock cycles
+    twr: u8,
+    /// Row cycle delay / row refresh cycle, in master clock cycles
+    trc_trfc: u8,
+    /// Row precharge delay, in master clock cycles
+    trp: u8,
+    /// Row to column delay, in master clock cycles
+    trcd: u8,
+    /// Active to precharge delay, in master clock cycles
+    tras: u8,
+    /// Exit self refresh to active delay, in master clock cycles
+    txsr: u8,
+    /// Refresh interval, in microseconds, between consecutive auto-refresh
+    /// commands once the device is in normal mode
+    refresh_interval_us: u32,
+}
+
+impl SdramcBuilder {
+    /// Start building an SDRAM configuration. `mck` is the current master
+    /// clock frequency, used to convert the refresh interval into the
+    /// SDRAMC's refresh counter, and defaults are set for a common 512Mbit
+    /// x16 SDRAM part.
+    pub fn new(sdramc: SDRAMC, mck: Hertz) -> Self {
+        Self {
+            sdramc,
+            mck,
+            columns: ColumnBits::Ten,
+            rows: RowBits::Thirteen,
+            banks: BankCount::Four,
+            cas_latency: CasLatency::Two,
+            twr: 2,
+            trc_trfc: 7,
+            trp: 2,
+            trcd: 2,
+            tras: 5,
+            txsr: 7,
+            refresh_interval_us: 7,
+        }
+    }
+
+    /// Set the number of column address bits.
+    pub fn columns(mut self, columns: ColumnBits) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Set the number of row address bits.
+    pub fn rows(mut self, rows: RowBits) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Set the number of internal banks.
+    pub fn banks(mut self, banks: BankCount) -> Self {
+        self.banks = banks;
+        self
+    }
+
+    /// Set the CAS latency.
+    pub fn cas_latency(mut self, cas_latency: CasLatency) -> Self {
+        self.cas_latency = cas_latency;
+        self
+    }
+
+    /// Set the write recovery delay, in master clock cycles.
+    pub fn write_recovery_cycles(mut self, cycles: u8) -> Self {
+        self.twr = cycles;
+        self
+    }
+
+    /// Set the row cycle / row refresh cycle delay, in master clock cycles.
+    pub fn row_cycle_cycles(mut self, cycles: u8) -> Self {
+        self.trc_trfc = cycles;
+        self
+    }
+
+    /// Set the row precharge delay, in master clock cycles.
+    pub fn row_precharge_cycles(mut self, cycles: u8) -> Self {
+        self.trp = cycles;
+        self
+    }
+
+    /// Set the row to column delay, in master clock cycles.
+    pub fn row_to_column_cycles(mut self, cycles: u8) -> Self {
+        self.trcd = cycles;
+        self
+    }
+
+    /// Set the active to precharge delay, in master clock cycles.
+    pub fn active_to_precharge_cycles(mut self, cycles: u8) -> Self {
+        self.tras = cycles;
+        self
+    }
+
+    /// Set the exit self refresh to active delay, in master clock cycles.
+    pub fn exit_self_refresh_cycles(mut self, cycles: u8) -> Self {
+        self.txsr = cycles;
+        self
+    }
+
+    /// Set the interval, in microseconds, between auto-refresh commands once
+    /// the device reaches normal mode.
+    pub fn refresh_interval_us(mut self, refresh_interval_us: u32) -> Self {
+        self.refresh_interval_us = refresh_interval_us;
+        self
+    }
+
+    /// Program the SDRAMC configuration registers, then drive the chip
+    /// through the JEDEC-mandated power up sequence: a NOP, followed by a
+    /// precharge-all, followed by eight auto-refresh cycles, followed by a
+    /// load-mode-register, before finally switching to normal mode and
+    /// enabling periodic auto-refresh. Each command is followed by a dummy
+    /// access to the SDRAM address space, as required for the controller to
+    /// actually issue it on the bus.
+    ///
+    /// # Safety
+    ///
+    /// The caller must have already muxed the EBI pins to their SDRAM
+    /// peripheral function and enabled the SDRAM chip select in the bus
+    /// matrix; this function has no way to verify either.
+    pub unsafe fn init(self) -> Sdramc {
+        self.sdramc.cr.write(|w| unsafe {
+            w.nc()
+                .bits(self.columns.bits())
+                .nr()
+                .bits(self.rows.bits())
+                .nb()
+                .bits(self.banks.bits())
+                .cas()
+                .bits(self.cas_latency.bits())
+                .dbw()
+                .clear_bit()
+                .twr()
+                .bits(self.twr)
+                .trc_trfc()
+                .bits(self.trc_trfc)
+                .trp()
+                .bits(self.trp)
+                .trcd()
+                .bits(self.trcd)
+                .tras()
+                .bits(self.tras)
+                .txsr()
+                .bits(self.txsr)
+        });
+
+        // This driver only targets plain SDRAM, not low-power SDRAM.
+        self.sdramc.mdr.write(|w| unsafe { w.md().bits(0) });
+
+        let sdram = SDRAM_BASE_ADDRESS as *mut u32;
+
+        // Issue a NOP command, then a dummy access, per the datasheet's
+        // power up sequence.
+        self.sdramc.mr.write(|w| unsafe { w.mode().bits(0x1) });
+        sdram.write_volatile(0);
+
+        // Issue an all-banks precharge, then a dummy access.
+        self.sdramc.mr.write(|w| unsafe { w.mode().bits(0x2) });
+        sdram.write_volatile(0);
+
+        // Issue eight auto-refresh cycles, each followed by a dummy access.
+        for _ in 0..8 {
+            self.sdramc.mr.write(|w| unsafe { w.mode().bits(0x4) });
+            sdram.write_volatile(0);
+        }
+
+        // Issue a load-mode-register command, then a dummy access.
+        self.sdramc.mr.write(|w| unsafe { w.mode().bits(0x3) });
+        sdram.write_volatile(0);
+
+        // Finally, switch to normal mode.
+        self.sdramc.mr.write(|w| unsafe { w.mode().bits(0x0) });
+        sdram.write_volatile(0);
+
+        // Program the refresh timer so the SDRAMC keeps the contents alive
+        // going forward.
+        let refresh_count = self.refresh_interval_us * (self.mck.0 / 1_000_000);
+        self.sdramc
+            .tr
+            .write(|w| unsafe { w.count().bits(refresh_count) });
+
+        Sdramc {
+            sdramc: self.sdramc,
+            columns: self.columns,
+            rows: self.rows,
+            banks: self.banks,
+        }
+    }
+}
+
+/// A fully configured SDRAM controller, with the external chip brought up
+/// and mapped at [`SDRAM_BASE_ADDRESS`].
+pub struct Sdramc {
+    sdramc: SDRAMC,
+    columns: ColumnBits,
+    rows: RowBits,
+    banks: BankCount,
+}
+
+impl core::ops::Deref for Sdramc {
+    type Target = SDRAMC;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sdramc
+    }
+}
+
+impl core::ops::DerefMut for Sdramc {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sdramc
+    }
+}
+
+impl Sdramc {
+    /// Total capacity of the mapped SDRAM, in bytes. The device is wired for
+    /// a 16-bit data bus, so this is `columns * rows * banks * 2`.
+    pub fn capacity_bytes(&self) -> usize {
+        let columns = 1usize << self.columns.count();
+        let rows = 1usize << self.rows.count();
+        columns * rows * self.banks.count() as usize * 2
+    }
+
+    /// Borrow the external SDRAM as a slice of 32-bit words, suitable for use
+    /// as a heap backing store or a framebuffer.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no other code is concurrently accessing the
+    /// same memory, since this creates a new `&'static mut` on every call.
+    pub unsafe fn memory(&mut self) -> &'static mut [u32] {
+        let len = self.capacity_bytes() / core::mem::size_of::<u32>();
+        core::slice::from_raw_parts_mut(SDRAM_BASE_ADDRESS as *mut u32, len)
+    }
+}
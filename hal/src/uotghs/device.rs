@@ -0,0 +1,59 @@
+//! Bring-up for the Due's "Native" USB port in device mode.
+//!
+//! `super::UsbOtgHs` only drives UOTGHS as a Host (it implements
+//! `usb_host`'s `USBHost` trait against host-side pipes). A
+//! native-port CDC-ACM console needs the other role: a `usb_device::bus::
+//! UsbBus` impl backed by UOTGHS's device-mode endpoint FIFOs (`EPTCFG`,
+//! `EPTCTL`, `EPTSTA`, the per-endpoint DPRAM banks, and SOF/suspend/resume
+//! handling), none of which exist anywhere in this crate yet. Without that,
+//! there's no `UsbBus` to construct a `usb_device::bus::UsbBusAllocator` or
+//! a `usbd_serial::SerialPort` from.
+//!
+//! What's safe to provide without that driver is to power up the peripheral
+//! clock and take ownership of the `uotg_id`/`uotg_vbof` pins, as `super::
+//! UsbOtgHs::new` does with the pins for Host mode (it leaves the
+//! peripheral clock as it finds it). `UdpBringup` does
+//! just that, so the peripheral and pins are held (and not accidentally
+//! also handed to a `UsbOtgHs`) until a device-mode driver lands to build
+//! the `UsbBus` impl on top of it.
+
+use crate::clock::SystemClocks;
+use crate::gpio::{Pb10, Pb11, PfA};
+use crate::target_device::UOTGHS;
+use crate::PeripheralID;
+
+/// Holds the UOTGHS peripheral and its `uotg_id`/`uotg_vbof` pins with the
+/// peripheral clock enabled, ready for a device-mode driver that doesn't
+/// exist in this crate yet. See the module docs for what's missing before
+/// this can back a `usb_device::bus::UsbBusAllocator`.
+pub struct UdpBringup {
+    uotghs_p: UOTGHS,
+    uotg_id: Option<Pb11<PfA>>,
+    uotg_vbof: Option<Pb10<PfA>>,
+}
+
+impl UdpBringup {
+    /// Enable the UOTGHS peripheral clock and take ownership of `uotghs_p`
+    /// and its optional `uotg_id`/`uotg_vbof` pins, mirroring
+    /// `super::UsbOtgHs::new`'s pin handling for Host mode.
+    pub fn new(
+        uotghs_p: UOTGHS,
+        uotg_id: Option<Pb11<PfA>>,
+        uotg_vbof: Option<Pb10<PfA>>,
+        clocks: &mut SystemClocks,
+    ) -> Self {
+        clocks.enable_peripheral_clock(PeripheralID::UOTGHS);
+        Self {
+            uotghs_p,
+            uotg_id,
+            uotg_vbof,
+        }
+    }
+
+    /// Give back the peripheral and pins, e.g. to hand them to
+    /// `super::UsbOtgHs::new` instead once the caller decides it wants Host
+    /// mode after all.
+    pub fn free(self) -> (UOTGHS, Option<Pb11<PfA>>, Option<Pb10<PfA>>) {
+        (self.uotghs_p, self.uotg_id, self.uotg_vbof)
+    }
+}
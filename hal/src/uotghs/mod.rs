@@ -1,6 +1,7 @@
 use crate::clock::SystemClocks;
 use crate::gpio::{Pb10, Pb11, PfA};
 use crate::target_device::generic::Variant;
+use crate::target_device::uotghs::hstpipcfg::{PBK_A, PTOKEN_A, PTYPE_A};
 use crate::target_device::uotghs::sr::SPEED_A;
 use crate::target_device::UOTGHS;
 use starb::{Reader, RingBuffer, Writer};
@@ -10,12 +11,16 @@ use usb_host::{
     USBHost, WValue,
 };
 
+#[cfg(feature = "usb-device")]
+pub mod device;
 pub mod pipe;
-use crate::uotghs::pipe::{Pipe, MAX_PIPES};
+use crate::uotghs::pipe::{Pipe, SetupPacket, MAX_PIPES};
 
 // TODO: verify this number
 const MAX_DEVICES: usize = 4;
-const NAK_LIMIT: usize = 15;
+/// Wall-clock budget allowed for NAK retries on a single IN/OUT transfer,
+/// replacing what used to be a raw spin count.
+const NAK_TIMEOUT_MS: u64 = 50;
 
 /// Errors that can result from host operations
 pub enum HostError {
@@ -23,6 +28,27 @@ pub enum HostError {
     DriverError(DriverError),
 }
 
+/// Millisecond-resolution monotonic clock fed into driver ticks and used to
+/// bound NAK retries by elapsed time. Implement this against a free-running
+/// TC channel or SysTick.
+pub trait HostClock {
+    /// Milliseconds elapsed since some arbitrary epoch. Must be
+    /// monotonically non-decreasing for as long as the host is in use.
+    fn now_ms(&self) -> u64;
+}
+
+/// Clock used when [`UsbOtgHs::new`] isn't given one: jiffies stay fixed at
+/// `0`, preserving the host's previous behavior.
+struct NullClock;
+
+impl HostClock for NullClock {
+    fn now_ms(&self) -> u64 {
+        0
+    }
+}
+
+static NULL_CLOCK: NullClock = NullClock;
+
 /// Models the Host state of the UOTGHS controller.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HostState {
@@ -236,12 +262,93 @@ impl DeviceTable {
         None
     }
 
-    /// Remove the device at address `addr`.
-    fn remove(&mut self, addr: u8) -> Option<Device> {
+    /// Remove the device at address `addr`, freeing any hardware pipes it
+    /// had allocated.
+    fn remove(&mut self, uotghs_p: &mut UOTGHS, pipes: &mut PipeTable, addr: u8) -> Option<Device> {
+        pipes.release(uotghs_p, addr);
         self.tbl[addr as usize].take()
     }
 }
 
+/// Maps a logical endpoint (device address + endpoint number) onto one of
+/// the controller's `MAX_PIPES` hardware pipes, allocating and configuring a
+/// free pipe the first time an endpoint is used and reusing it on every
+/// later transfer.
+struct PipeTable {
+    /// `(address, endpoint_num)` of whichever endpoint currently owns each
+    /// hardware pipe, indexed by pipe number; `None` if the pipe is free.
+    owners: [Option<(u8, u8)>; MAX_PIPES as usize],
+}
+
+impl PipeTable {
+    fn new() -> Self {
+        Self {
+            owners: [None; MAX_PIPES as usize],
+        }
+    }
+
+    /// Find the pipe already configured for `ep`, or allocate and configure
+    /// a free one for it.
+    fn pipe_for<'a>(
+        &mut self,
+        uotghs_p: &'a mut UOTGHS,
+        ep: &dyn Endpoint,
+    ) -> Result<Pipe<'a>, TransferError> {
+        let address = ep.address();
+        let ep_num = ep.endpoint_num();
+
+        if let Some(pipe_num) = self.owners.iter().position(|o| *o == Some((address, ep_num))) {
+            return Ok(Pipe::get(uotghs_p, pipe_num as u8)?);
+        }
+
+        let ep_type = match ep.transfer_type() {
+            TransferType::Control => PTYPE_A::CTRL,
+            TransferType::Isochronous => PTYPE_A::ISO,
+            TransferType::Bulk => PTYPE_A::BULK,
+            TransferType::Interrupt => PTYPE_A::INTRPT,
+        };
+        let ep_dir = match ep.direction() {
+            Direction::In => PTOKEN_A::IN,
+            Direction::Out => PTOKEN_A::OUT,
+        };
+
+        // Find a free slot from our own cache rather than re-querying each
+        // pipe's hardware enable bit, so there's a single source of truth
+        // for which pipes are in use.
+        let pipe_num = self
+            .owners
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, o)| o.is_none())
+            .map(|(n, _)| n as u8)
+            .ok_or(pipe::PipeError::OutOfPipes)?;
+
+        let mut pipe = Pipe::get(uotghs_p, pipe_num)?;
+        pipe.init_n(
+            address,
+            ep_num,
+            ep_type,
+            ep_dir,
+            ep.max_packet_size(),
+            0,
+            PBK_A::_1_BANK,
+        )?;
+        self.owners[pipe_num as usize] = Some((address, ep_num));
+        Ok(pipe)
+    }
+
+    /// Free every pipe owned by device `address`.
+    fn release(&mut self, uotghs_p: &mut UOTGHS, address: u8) {
+        for pipe_num in 0..MAX_PIPES {
+            if matches!(self.owners[pipe_num as usize], Some((a, _)) if a == address) {
+                Pipe::get(uotghs_p, pipe_num).unwrap().free();
+                self.owners[pipe_num as usize] = None;
+            }
+        }
+    }
+}
+
 pub struct UsbOtgHs {
     uotghs_p: UOTGHS,
 
@@ -252,10 +359,25 @@ pub struct UsbOtgHs {
     host_state: HostState,
 
     devices: DeviceTable,
+    pipe_table: PipeTable,
+
+    clock: &'static dyn HostClock,
 }
 
 impl UsbOtgHs {
     pub fn new(uotghs_p: UOTGHS, uotg_id: Option<Pb11<PfA>>, uotg_vbof: Option<Pb10<PfA>>) -> Self {
+        Self::with_clock(uotghs_p, uotg_id, uotg_vbof, &NULL_CLOCK)
+    }
+
+    /// As [`Self::new`], additionally supplying a [`HostClock`] used to feed
+    /// real millisecond jiffies into each driver's `tick` and to bound NAK
+    /// retries on pipe transfers by elapsed time.
+    pub fn with_clock(
+        uotghs_p: UOTGHS,
+        uotg_id: Option<Pb11<PfA>>,
+        uotg_vbof: Option<Pb10<PfA>>,
+        clock: &'static dyn HostClock,
+    ) -> Self {
         let (eventr, _) = unsafe { EVENTS.split() };
         Self {
             uotghs_p,
@@ -264,6 +386,8 @@ impl UsbOtgHs {
             events: eventr,
             host_state: HostState::NoVbus,
             devices: DeviceTable::new(),
+            pipe_table: PipeTable::new(),
+            clock,
         }
     }
 
@@ -391,13 +515,13 @@ impl UsbOtgHs {
                 }
             }
             HostState::Attached(TaskState::Running) => {
-                // TODO: find some way to query a monotonic clock
-                let jiffies = 0;
+                let jiffies = self.clock.now_ms();
                 for d in &mut drivers[..] {
                     if let Err(e) = d.tick(jiffies, self) {
                         if let DriverError::Permanent(a, _) = e {
                             d.remove_device(a);
-                            self.devices.remove(a);
+                            self.devices
+                                .remove(&mut self.uotghs_p, &mut self.pipe_table, a);
                         }
                     }
                 }
@@ -467,10 +591,15 @@ impl USBHost for UsbOtgHs {
         w_index: u16,
         buf: Option<&mut [u8]>,
     ) -> Result<usize, TransferError> {
-        // TODO: need support for mapping endpoints to pipes
-        let mut pipe = self.pipe_table.pipe_for(ep);
-        // TODO: actually issue the request on the specified pipe
-        let len = pipe.control_transfer(ep, bm_request_type, b_request, w_value, w_index, buf)?;
+        let setup = SetupPacket {
+            request_type: u8::from(bm_request_type),
+            request: b_request as u8,
+            value: u16::from(w_value),
+            index: w_index,
+            length: buf.as_ref().map_or(0, |b| b.len()) as u16,
+        };
+        let mut pipe = self.pipe_table.pipe_for(&mut self.uotghs_p, ep)?;
+        let len = pipe.control_transfer(ep, &setup, buf, self.clock, NAK_TIMEOUT_MS)?;
         Ok(len)
     }
 
@@ -479,18 +608,14 @@ impl USBHost for UsbOtgHs {
         ep: &mut dyn Endpoint,
         buf: &mut [u8],
     ) -> Result<usize, TransferError> {
-        // TODO: need support for mapping endpoints to pipes
-        let mut pipe = self.pipe_table.pipe_for(ep);
-        // TODO: actually transfer from the specified pipe into the buffer
-        let len = pipe.in_transfer(ep, buf, NAK_LIMIT)?;
+        let mut pipe = self.pipe_table.pipe_for(&mut self.uotghs_p, ep)?;
+        let len = pipe.in_transfer(ep, buf, self.clock, NAK_TIMEOUT_MS)?;
         Ok(len)
     }
 
     fn out_transfer(&mut self, ep: &mut dyn Endpoint, buf: &[u8]) -> Result<usize, TransferError> {
-        // TODO: need support for mapping endpoint numbers to pipe numbers
-        let mut pipe = self.pipe_table.pipe_for(ep);
-        // TODO: actually transfer the buffer out on the specified pipe
-        let len = pipe.out_transfer(ep, buf, NAK_LIMIT)?;
+        let mut pipe = self.pipe_table.pipe_for(&mut self.uotghs_p, ep)?;
+        let len = pipe.out_transfer(ep, buf, self.clock, NAK_TIMEOUT_MS)?;
         Ok(len)
     }
 }
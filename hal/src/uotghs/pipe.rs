@@ -1,5 +1,8 @@
+use crate::target_device::generic::Variant;
 use crate::target_device::uotghs::hstpipcfg::{PBK_A, PTOKEN_A, PTYPE_A};
 use crate::target_device::UOTGHS;
+use crate::uotghs::HostClock;
+use usb_host::{Endpoint, TransferError};
 
 // USB On-The-Go Interface RAM base address
 const UOTGHS_RAM_SIZE: usize = 0x8000;
@@ -7,6 +10,12 @@ const UOTGHS_RAM_ADDR: usize = 0x2018_0000;
 
 pub(crate) const MAX_PIPES: u8 = 10;
 
+/// Number of back-to-back NAKs a transfer tolerates before giving up.
+/// Matches the retry budget the SAMD21 host driver uses for the same
+/// problem: a device (e.g. a flash drive between reads) is allowed to
+/// stall the bus a little without failing the transfer outright.
+const NAK_LIMIT: u8 = 15;
+
 /// Errors that can result from operations on pipes
 #[derive(Debug, Clone, Copy)]
 pub enum PipeError {
@@ -20,6 +29,63 @@ pub enum PipeError {
     OutOfPipes,
     /// The pipe requested for this operation is invalid.
     InvalidOperation,
+    /// The device NAKed this transfer more than `NAK_LIMIT` times.
+    Nak,
+    /// The transfer did not complete before its deadline.
+    Timeout,
+    /// The device returned a STALL handshake.
+    Stall,
+    /// A CRC16 check failed on received data.
+    CrcError,
+    /// The received packet's PID was invalid or didn't match its
+    /// complement.
+    PidError,
+    /// The received data packet's PID didn't match the pipe's expected
+    /// data toggle (DATA0/DATA1).
+    DataToggleMismatch,
+    /// The pipe's FIFO overflowed (more data arrived than it could hold).
+    Overflow,
+    /// The pipe's FIFO underflowed (data was requested before it arrived).
+    Underflow,
+    /// `hstpiperr`'s error counter saturated without matching any of the
+    /// specific error flags above.
+    PipeHwError,
+}
+
+/// The 8-byte SETUP packet that begins every USB control transfer, in `usb_host`'s
+/// raw wire-format terms rather than the higher-level `RequestType`/`RequestCode`/
+/// `WValue` types it's usually assembled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetupPacket {
+    /// `bmRequestType`: transfer direction, request type, and recipient.
+    pub request_type: u8,
+    /// `bRequest`: the specific request being made.
+    pub request: u8,
+    /// `wValue`: request-specific parameter.
+    pub value: u16,
+    /// `wIndex`: request-specific parameter, often an index or offset.
+    pub index: u16,
+    /// `wLength`: number of bytes in the data stage, if any.
+    pub length: u16,
+}
+
+impl SetupPacket {
+    /// Serialize to the little-endian 8-byte wire layout.
+    fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[0] = self.request_type;
+        bytes[1] = self.request;
+        bytes[2..4].copy_from_slice(&self.value.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.index.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.length.to_le_bytes());
+        bytes
+    }
+
+    /// Whether this request's data/status stages flow device-to-host (the
+    /// high bit of `bmRequestType`).
+    fn device_to_host(&self) -> bool {
+        self.request_type & 0x80 != 0
+    }
 }
 
 pub struct Pipe<'a> {
@@ -28,6 +94,11 @@ pub struct Pipe<'a> {
 }
 
 impl<'a> Pipe<'a> {
+    /// The hardware pipe number this handle refers to.
+    pub fn number(&self) -> u8 {
+        self.pipe_num
+    }
+
     pub fn get(uotghs_p: &'a mut UOTGHS, pipe_num: u8) -> Result<Self, PipeError> {
         if pipe_num < MAX_PIPES {
             Ok(Self { uotghs_p, pipe_num })
@@ -36,32 +107,6 @@ impl<'a> Pipe<'a> {
         }
     }
 
-    pub fn alloc(
-        uotghs_p: &'a mut UOTGHS,
-        address: u8,
-        ep_num: u8,
-        ep_type: PTYPE_A,
-        ep_dir: PTOKEN_A,
-        ep_size: u16,
-        poll_freq: u8,
-        num_banks: PBK_A,
-    ) -> Result<Self, PipeError> {
-        for pipe_num in 1..MAX_PIPES {
-            let pipe = Self { uotghs_p, pipe_num };
-
-            if pipe.enabled() {
-                continue;
-            }
-
-            pipe.init_n(
-                address, ep_num, ep_type, ep_dir, ep_size, poll_freq, num_banks,
-            )?;
-            return Ok(pipe);
-        }
-
-        Err(PipeError::OutOfPipes)
-    }
-
     pub fn init_0(&mut self, address: u8, ep_size: u16) -> Result<(), PipeError> {
         if ep_size < 8 {
             return Err(PipeError::InvalidSize(ep_size));
@@ -297,41 +342,97 @@ impl<'a> Pipe<'a> {
         }
     }
 
-    pub fn read(&mut self, buf: &mut [u8]) {
-        let _len = self.uotghs_p.hstpipisr()[self.pipe_num as usize]
+    /// This pipe's configured bank size in bytes (`8 << psize`).
+    fn bank_size(&self, pipe_num: u8) -> usize {
+        8usize << self.uotghs_p.hstpipcfg()[pipe_num as usize].read().psize().bits()
+    }
+
+    /// This pipe's configured number of banks (1 for `_1_BANK`, etc).
+    fn num_banks(&self, pipe_num: u8) -> usize {
+        match self.uotghs_p.hstpipcfg()[pipe_num as usize].read().pbk().variant() {
+            Variant::Val(PBK_A::_1_BANK) => 1,
+            Variant::Val(PBK_A::_2_BANK) => 2,
+            Variant::Val(PBK_A::_3_BANK) => 3,
+            Variant::Res(_) => 1,
+        }
+    }
+
+    /// Byte offset into `UOTGHS_RAM` where this pipe's FIFO region begins.
+    /// The DPRAM is packed sequentially by each lower-numbered pipe's
+    /// configured size and bank count, not by raw pipe index, since pipes
+    /// are free to request any power-of-two size from 8 to 1024 bytes and
+    /// 1-3 banks each.
+    fn fifo_base(&self) -> usize {
+        (0..self.pipe_num)
+            .map(|n| self.bank_size(n) * self.num_banks(n))
+            .sum()
+    }
+
+    /// Byte offset of the bank currently being serviced by hardware
+    /// (`CURRBK`), so a double/triple-banked pipe can be filled or drained
+    /// in one bank by software while the other is in flight.
+    fn bank_offset(&mut self) -> usize {
+        let bank = self.uotghs_p.hstpipisr()[self.pipe_num as usize]
+            .read()
+            .currbk()
+            .bits() as usize;
+        self.fifo_base() + bank * self.bank_size(self.pipe_num)
+    }
+
+    /// Copy the current bank's received data into `buf`, returning the
+    /// number of bytes the device actually sent (`PBYCT`), which may be
+    /// less than `buf.len()` for a short packet. Bounded by this pipe's
+    /// configured bank size either way.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let got = self.uotghs_p.hstpipisr()[self.pipe_num as usize]
             .read()
-            .pbyct();
+            .pbyct()
+            .bits() as usize;
+        let bank_size = self.bank_size(self.pipe_num);
+        let len = got.min(buf.len()).min(bank_size);
 
-        // The uotghs ram is divided into a segment for each pipe, where each
-        // segment is the size of a bus transfer. So we turn it into a slice of
-        // transfers, and get the correct slice index for our pipe, and return
-        // it as a pointer to the transfer data.
+        let base = self.bank_offset();
         let uotghs_ram =
             unsafe { core::slice::from_raw_parts(UOTGHS_RAM_ADDR as *const u8, UOTGHS_RAM_SIZE) };
-        let pipe_ram = &uotghs_ram[self.pipe_num as usize..(self.pipe_num as usize + buf.len())];
+        let pipe_ram = &uotghs_ram[base..base + len];
+
+        buf[..len].copy_from_slice(pipe_ram);
+        len
+    }
 
-        buf.copy_from_slice(pipe_ram);
+    /// Whether the last completed IN transfer on this pipe was a short
+    /// packet (fewer bytes than the endpoint's max packet size), signaling
+    /// the device has no more data to send this transfer. Clears the flag.
+    fn short_packet(&mut self) -> bool {
+        let sp = self.uotghs_p.hstpipisr()[self.pipe_num as usize]
+            .read()
+            .shortpacketi()
+            .bit_is_set();
+        if sp {
+            self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                .write_with_zero(|w| w.shortpacketic().set_bit());
+        }
+        sp
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Result<(), PipeError> {
+    /// Copy `buf` into the current bank's FIFO region, returning the number
+    /// of bytes actually written (bounded by this pipe's configured bank
+    /// size, since a single packet can never exceed it).
+    pub fn write(&mut self, buf: &[u8]) -> Result<usize, PipeError> {
         if !self.enabled() {
             return Err(PipeError::InvalidOperation);
         }
-        let _len = self.uotghs_p.hstpipisr()[self.pipe_num as usize]
-            .read()
-            .pbyct();
 
-        // The uotghs ram is divided into a segment for each pipe, where each
-        // segment is the size of a bus transfer. So we turn it into a slice of
-        // transfers, and get the correct slice index for our pipe, and return
-        // it as a pointer to the transfer data.
+        let bank_size = self.bank_size(self.pipe_num);
+        let len = buf.len().min(bank_size);
+
+        let base = self.bank_offset();
         let uotghs_ram =
             unsafe { core::slice::from_raw_parts_mut(UOTGHS_RAM_ADDR as *mut u8, UOTGHS_RAM_SIZE) };
-        let pipe_ram =
-            &mut uotghs_ram[self.pipe_num as usize..(self.pipe_num as usize + buf.len())];
+        let pipe_ram = &mut uotghs_ram[base..base + len];
 
-        pipe_ram.copy_from_slice(buf);
-        Ok(())
+        pipe_ram.copy_from_slice(&buf[..len]);
+        Ok(len)
     }
 
     pub fn send(&mut self, token_type: PTOKEN_A) -> Result<(), PipeError> {
@@ -363,6 +464,73 @@ impl<'a> Pipe<'a> {
         Ok(())
     }
 
+    /// The expected data-toggle (DATA0/DATA1, `false`/`true`) this pipe
+    /// will use for its next transfer.
+    pub fn get_data_toggle(&mut self) -> bool {
+        self.uotghs_p.hstpipcfg()[self.pipe_num as usize]
+            .read()
+            .dtseq()
+            .bits()
+            != 0
+    }
+
+    /// Force this pipe's data toggle to `toggle` (`true` = DATA1, `false` =
+    /// DATA0) ahead of a transfer. Needed because a pipe's toggle resets to
+    /// DATA0 whenever it's freed and reallocated (`free`/`alloc`), so an
+    /// endpoint that's given up and later reacquired its pipe must restore
+    /// its own last toggle rather than silently restarting the sequence.
+    pub fn set_data_toggle(&mut self, toggle: bool) {
+        self.uotghs_p.hstpipcfg()[self.pipe_num as usize]
+            .modify(|_, w| unsafe { w.dtseq().bits(if toggle { 1 } else { 0 }) });
+    }
+
+    /// Check this pipe's hardware error flags (stall, overflow, underflow,
+    /// and the CRC16/PID/data-toggle/timeout sub-errors reported through
+    /// `hstpiperr`), map the first one found to a `PipeError` and clear
+    /// that one; any other flags stay set for a later call. Returns `Ok(())`
+    /// if nothing is flagged.
+    pub fn check_status(&mut self) -> Result<(), PipeError> {
+        let isr = self.uotghs_p.hstpipisr()[self.pipe_num as usize].read();
+
+        if isr.rxstalldi().bit_is_set() {
+            self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                .write_with_zero(|w| w.rxstalldic().set_bit());
+            return Err(PipeError::Stall);
+        }
+        if isr.overfi().bit_is_set() {
+            self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                .write_with_zero(|w| w.overfic().set_bit());
+            return Err(PipeError::Overflow);
+        }
+        if isr.underfi().bit_is_set() {
+            self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                .write_with_zero(|w| w.underfic().set_bit());
+            return Err(PipeError::Underflow);
+        }
+        if isr.perri().bit_is_set() {
+            let err = self.uotghs_p.hstpiperr()[self.pipe_num as usize].read();
+            let result = if err.crc16().bit_is_set() {
+                Err(PipeError::CrcError)
+            } else if err.timeout().bit_is_set() {
+                Err(PipeError::Timeout)
+            } else if err.pid().bit_is_set() {
+                Err(PipeError::PidError)
+            } else if err.datapid().bit_is_set() {
+                Err(PipeError::PidError)
+            } else if err.datatgl().bit_is_set() {
+                Err(PipeError::DataToggleMismatch)
+            } else {
+                Err(PipeError::PipeHwError)
+            };
+            self.uotghs_p.hstpiperr()[self.pipe_num as usize].write(|w| unsafe { w.bits(0) });
+            self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                .write_with_zero(|w| w.perric().set_bit());
+            return result;
+        }
+
+        Ok(())
+    }
+
     pub fn is_transfer_complete(&mut self, token_type: PTOKEN_A) -> bool {
         match token_type {
             PTOKEN_A::SETUP => {
@@ -413,4 +581,171 @@ impl<'a> Pipe<'a> {
         }
         false
     }
+
+    /// Drive a single token (`SETUP`/`IN`/`OUT`) to completion, tolerating
+    /// up to `NAK_LIMIT` NAKs from the device and bounding the whole wait
+    /// by `timeout_ms` (measured against `clock`), so a NAKing or
+    /// unresponsive device doesn't stall the caller forever.
+    fn run_transfer(
+        &mut self,
+        token: PTOKEN_A,
+        clock: &dyn HostClock,
+        timeout_ms: u64,
+    ) -> Result<(), PipeError> {
+        let deadline_ms = clock.now_ms() + timeout_ms;
+        let mut naks_left = NAK_LIMIT;
+        self.send(token)?;
+        loop {
+            if self.is_transfer_complete(token) {
+                return Ok(());
+            }
+
+            if self.uotghs_p.hstpipisr()[self.pipe_num as usize]
+                .read()
+                .nakedi()
+                .bit_is_set()
+            {
+                self.uotghs_p.hstpipicr()[self.pipe_num as usize]
+                    .write_with_zero(|w| w.nakedic().set_bit());
+                if naks_left == 0 {
+                    return Err(PipeError::Nak);
+                }
+                naks_left -= 1;
+            }
+
+            if clock.now_ms() >= deadline_ms {
+                return Err(PipeError::Timeout);
+            }
+        }
+    }
+
+    /// Run the three USB control transfer phases on this pipe: write the
+    /// 8-byte `setup` packet and issue a SETUP token; if `data` is given,
+    /// shuttle up to `ep`'s max packet size per IN/OUT token (direction
+    /// taken from `setup.request_type`'s direction bit) until `data` is
+    /// full, `setup.length` bytes have moved, or a short packet is seen;
+    /// then issue the opposite-direction zero-length status token.
+    ///
+    /// A control transfer's data toggles are fixed by the USB spec rather
+    /// than carried over from the endpoint like `in_transfer`/`out_transfer`
+    /// do: SETUP is always DATA0, and the data/status stages always start at
+    /// DATA1. Since `PipeTable::pipe_for` hands back the same cached pipe
+    /// for every transfer to an endpoint without resetting it, the toggle is
+    /// forced explicitly at each stage instead of trusting whatever it was
+    /// left at by the previous transfer.
+    pub fn control_transfer(
+        &mut self,
+        ep: &mut dyn Endpoint,
+        setup: &SetupPacket,
+        data: Option<&mut [u8]>,
+        clock: &dyn HostClock,
+        timeout_ms: u64,
+    ) -> Result<usize, PipeError> {
+        self.set_data_toggle(false);
+        self.write(&setup.to_bytes())?;
+        self.run_transfer(PTOKEN_A::SETUP, clock, timeout_ms)?;
+
+        let device_to_host = setup.device_to_host();
+        let ep_size = ep.max_packet_size() as usize;
+        let mut transferred = 0;
+
+        if let Some(buf) = data {
+            self.set_data_toggle(true);
+            let want = (setup.length as usize).min(buf.len());
+            while transferred < want {
+                let chunk = (want - transferred).min(ep_size);
+                if device_to_host {
+                    self.run_transfer(PTOKEN_A::IN, clock, timeout_ms)?;
+                    let got = self.read(&mut buf[transferred..transferred + chunk]);
+                    let short = self.short_packet();
+                    transferred += got;
+                    if short || got < ep_size {
+                        break;
+                    }
+                } else {
+                    self.write(&buf[transferred..transferred + chunk])?;
+                    self.run_transfer(PTOKEN_A::OUT, clock, timeout_ms)?;
+                    transferred += chunk;
+                    if chunk < ep_size {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Status stage: opposite direction of the data stage, zero length,
+        // always DATA1.
+        let status_token = if device_to_host {
+            PTOKEN_A::OUT
+        } else {
+            PTOKEN_A::IN
+        };
+        self.set_data_toggle(true);
+        self.run_transfer(status_token, clock, timeout_ms)?;
+
+        Ok(transferred)
+    }
+
+    /// Run a single IN transfer, waiting for it to complete until
+    /// `timeout_ms` milliseconds (as measured by `clock`) have elapsed,
+    /// tolerating up to `NAK_LIMIT` NAKs along the way. Restores `ep`'s last
+    /// IN data toggle before the transfer and saves the resulting toggle
+    /// back to `ep` afterward, so the sequence survives the pipe being
+    /// freed and reacquired between transfers.
+    pub fn in_transfer(
+        &mut self,
+        ep: &mut dyn Endpoint,
+        buf: &mut [u8],
+        clock: &dyn HostClock,
+        timeout_ms: u64,
+    ) -> Result<usize, PipeError> {
+        self.set_data_toggle(ep.in_toggle());
+        self.run_transfer(PTOKEN_A::IN, clock, timeout_ms)?;
+        let len = self.read(buf);
+        ep.set_in_toggle(self.get_data_toggle());
+        Ok(len)
+    }
+
+    /// Run a single OUT transfer, waiting for it to complete until
+    /// `timeout_ms` milliseconds (as measured by `clock`) have elapsed,
+    /// tolerating up to `NAK_LIMIT` NAKs along the way. Restores `ep`'s last
+    /// OUT data toggle before the transfer and saves the resulting toggle
+    /// back to `ep` afterward, so the sequence survives the pipe being
+    /// freed and reacquired between transfers.
+    pub fn out_transfer(
+        &mut self,
+        ep: &mut dyn Endpoint,
+        buf: &[u8],
+        clock: &dyn HostClock,
+        timeout_ms: u64,
+    ) -> Result<usize, PipeError> {
+        self.write(buf)?;
+        self.set_data_toggle(ep.out_toggle());
+        self.run_transfer(PTOKEN_A::OUT, clock, timeout_ms)?;
+        ep.set_out_toggle(self.get_data_toggle());
+        Ok(buf.len())
+    }
+}
+
+impl From<PipeError> for TransferError {
+    fn from(e: PipeError) -> Self {
+        match e {
+            PipeError::OutOfRange(_) => TransferError::Permanent("pipe out of range"),
+            PipeError::InvalidSize(_) => TransferError::Permanent("invalid pipe size"),
+            PipeError::InvalidConfiguration(_) => TransferError::Permanent("pipe rejected configuration"),
+            PipeError::OutOfPipes => TransferError::Permanent("no pipes available"),
+            PipeError::InvalidOperation => TransferError::Permanent("pipe not enabled"),
+            PipeError::Nak => TransferError::Permanent("device naked transfer too many times"),
+            PipeError::Timeout => TransferError::Permanent("transfer timed out"),
+            PipeError::Stall => TransferError::Permanent("device returned a STALL handshake"),
+            PipeError::CrcError => TransferError::Permanent("CRC16 check failed on received data"),
+            PipeError::PidError => TransferError::Permanent("received packet had an invalid PID"),
+            PipeError::DataToggleMismatch => {
+                TransferError::Permanent("received packet's data toggle didn't match")
+            }
+            PipeError::Overflow => TransferError::Permanent("pipe FIFO overflowed"),
+            PipeError::Underflow => TransferError::Permanent("pipe FIFO underflowed"),
+            PipeError::PipeHwError => TransferError::Permanent("pipe hardware error counter saturated"),
+        }
+    }
 }